@@ -2,10 +2,24 @@ use std::env;
 use std::fs;
 use std::io::{self, Write};
 use std::process;
+use std::thread;
 
-use rlox::{interpreter::Interpreter, parser::Parser, result::Error, scanner::Scanner};
+use rlox::interpreter::{Interpreter, STACK_SIZE};
+use rlox::result::Error;
 
 fn main() {
+    // The main thread doesn't have enough stack for the maximum call depth.
+    let cli = thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(run_cli)
+        .expect("Can't spawn interpreter thread");
+
+    if cli.join().is_err() {
+        process::exit(101);
+    }
+}
+
+fn run_cli() {
     let args: Vec<String> = env::args().collect();
 
     match args.len() {
@@ -20,6 +34,7 @@ fn run_file(path: &str) {
 
     let out = io::stdout();
     let mut interpreter = Interpreter::new(out);
+    interpreter.set_path(path);
 
     if let Err(err) = run(&mut interpreter, &content) {
         eprintln!("{}", err);
@@ -52,10 +67,5 @@ fn run_prompt() {
 }
 
 fn run<W: io::Write>(interpreter: &mut Interpreter<W>, source: &str) -> Result<(), Error> {
-    let tokens = Scanner::scan(source)?;
-    let statements = Parser::parse(tokens).map_err(|e| e[0].clone())?;
-
-    interpreter.interpret(&statements)?;
-
-    Ok(())
+    rlox::run(interpreter, source, |warning| eprintln!("{}", warning))
 }
@@ -0,0 +1,18 @@
+use std::process::Command;
+use std::str;
+
+#[test]
+fn warnings_are_reported_before_runtime_errors() {
+    let output = Command::new(env!("CARGO_BIN_EXE_rlox-cli"))
+        .arg("tests/fixtures/warning_then_error.lox")
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(str::from_utf8(&output.stdout).unwrap(), "other\n");
+    assert_eq!(
+        str::from_utf8(&output.stderr).unwrap(),
+        "Warning [line: 3]: Unreachable match arm.\n\
+         Runtime error [line: 5]: Operands must be numbers or strings.\n"
+    );
+}
@@ -1,3 +1,8 @@
+use std::cell::Cell;
+use std::rc::Rc;
+
+use crate::bigint::BigInt;
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum TokenKind {
     // Single-character tokens.
@@ -5,19 +10,43 @@ pub enum TokenKind {
     RightParen,
     LeftBrace,
     RightBrace,
+    LeftBracket,
+    RightBracket,
     Coma,
+    Colon,
     Dot,
     Minus,
     Plus,
+    Question,
     Semicolon,
     Slash,
     Star,
+    Percent,
+    Ampersand,
+    Pipe,
+    Caret,
+    Tilde,
 
     // One or two character tokens.
     Bang,
     BangEqual,
     Equal,
     EqualEqual,
+    FatArrow,
+    DotDot,
+    DotDotEqual,
+    QuestionQuestion,
+    QuestionQuestionEqual,
+    TildeSlash,
+    StarStar,
+    PlusEqual,
+    PlusPlus,
+    MinusEqual,
+    MinusMinus,
+    StarEqual,
+    SlashEqual,
+    LessLess,
+    GreaterGreater,
     Greater,
     GreaterEqual,
     Less,
@@ -26,27 +55,46 @@ pub enum TokenKind {
     // Literals.
     Identifier(String),
     String(String),
-    Number(f32),
+    // String segment preceding an interpolated expression.
+    Interpolation(String),
+    // String segment between two interpolated expressions.
+    InterpolationMiddle(String),
+    // String segment following the last interpolated expression.
+    InterpolationEnd(String),
+    Integer(i64),
+    BigInt(BigInt),
+    Number(f64),
 
     // Keywords.
     And,
+    As,
+    Break,
+    Catch,
     Class,
+    Const,
+    Continue,
     Else,
     False,
+    Finally,
     Function,
     For,
     If,
+    Import,
+    In,
+    Match,
     Nil,
     Or,
     Print,
     Return,
     Super,
     This,
+    Throw,
     True,
+    Try,
     Var,
     While,
 
-    EOF,
+    Eof,
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -61,7 +109,15 @@ pub enum BinaryOp {
     Plus,
     Minus,
     Slash,
+    TildeSlash,
     Star,
+    StarStar,
+    Percent,
+    Ampersand,
+    Pipe,
+    Caret,
+    LessLess,
+    GreaterGreater,
     Equal,
     NotEqual,
     Greater,
@@ -70,66 +126,300 @@ pub enum BinaryOp {
     LessEqual,
 }
 
+#[derive(Debug, PartialEq)]
+pub enum LogicalOp {
+    And,
+    Or,
+    NilCoalesce,
+}
+
 #[derive(Debug, PartialEq)]
 pub enum UnaryOp {
     Not,
     Neg,
+    BitNot,
 }
 
 #[derive(Debug, PartialEq)]
 pub enum LiteralValue {
-    Number(f32),
+    Integer(i64),
+    BigInt(BigInt),
+    Number(f64),
     String(String),
     True,
     False,
     Nil,
 }
 
+/// Variable use. The resolver records the number of scopes between the use and the variable
+/// declaration, the depth is left empty for globals.
+#[derive(Debug, PartialEq)]
+pub struct VarRef {
+    pub id: Token,
+    pub depth: Cell<Option<usize>>,
+}
+
+impl VarRef {
+    pub fn new(id: Token) -> Self {
+        Self {
+            id,
+            depth: Cell::new(None),
+        }
+    }
+}
+
 #[derive(Debug, PartialEq)]
 pub enum Expr {
-    Binary(Box<Expr>, BinaryOp, Box<Expr>),
-    Unary(UnaryOp, Box<Expr>),
+    Binary(Box<Expr>, Token, BinaryOp, Box<Expr>),
+    Logical(Box<Expr>, LogicalOp, Box<Expr>),
+    Conditional(Box<Expr>, Box<Expr>, Box<Expr>),
+    Comma(Box<Expr>, Box<Expr>),
+    Unary(Token, UnaryOp, Box<Expr>),
     Grouping(Box<Expr>),
     Literal(LiteralValue),
-    Variable(Token),
-    Assign(Token, Box<Expr>),
+    Interpolation(Vec<Expr>),
+    Variable(VarRef),
+    Assign(VarRef, Box<Expr>),
+    Call(Box<Expr>, Token, Vec<Expr>),
+    Get(Box<Expr>, Token),
+    Set(Box<Expr>, Token, Box<Expr>),
+    NilCoalesceAssign(Box<Expr>, Box<Expr>),
+    // Assignment of the operator applied to the target and the operand, for `x += 1`, `++x` and
+    // `x++`. The flag is set when the previous value of the target is returned.
+    CompoundAssign(Box<Expr>, Token, BinaryOp, Box<Expr>, bool),
+    // Assignment of the elements of a tuple to the targets of a tuple expression, which nest.
+    DestructureAssign(Box<Expr>, Token, Box<Expr>),
+    List(Vec<Expr>),
+    Tuple(Vec<Expr>),
+    Map(Token, Vec<(Expr, Expr)>),
+    // Integer range, the flag is set when the upper bound is inclusive.
+    Range(Box<Expr>, Token, Box<Expr>, bool),
+    Index(Box<Expr>, Token, Box<Expr>),
+    IndexSet(Box<Expr>, Token, Box<Expr>, Box<Expr>),
+    This(VarRef),
+    Super(VarRef, Token),
 }
 
 pub trait ExprVisitor<T> {
-    fn visit_binary_expr(&mut self, left: &Expr, op: &BinaryOp, right: &Expr) -> T;
-    fn visit_unary_expr(&mut self, op: &UnaryOp, expr: &Expr) -> T;
+    fn visit_binary_expr(
+        &mut self,
+        left: &Expr,
+        operator: &Token,
+        op: &BinaryOp,
+        right: &Expr,
+    ) -> T;
+    fn visit_logical_expr(&mut self, left: &Expr, op: &LogicalOp, right: &Expr) -> T;
+    fn visit_conditional_expr(
+        &mut self,
+        condition: &Expr,
+        then_branch: &Expr,
+        else_branch: &Expr,
+    ) -> T;
+    fn visit_comma_expr(&mut self, left: &Expr, right: &Expr) -> T;
+    fn visit_unary_expr(&mut self, operator: &Token, op: &UnaryOp, expr: &Expr) -> T;
     fn visit_grouping_expr(&mut self, expr: &Expr) -> T;
     fn visit_literal_expr(&mut self, value: &LiteralValue) -> T;
-    fn visit_variable_expr(&mut self, id: &Token) -> T;
-    fn visit_assignment_expr(&mut self, id: &Token, expr: &Expr) -> T;
+    fn visit_interpolation_expr(&mut self, parts: &[Expr]) -> T;
+    fn visit_variable_expr(&mut self, var: &VarRef) -> T;
+    fn visit_assignment_expr(&mut self, var: &VarRef, expr: &Expr) -> T;
+    fn visit_call_expr(&mut self, callee: &Expr, paren: &Token, args: &[Expr]) -> T;
+    fn visit_get_expr(&mut self, object: &Expr, name: &Token) -> T;
+    fn visit_set_expr(&mut self, object: &Expr, name: &Token, value: &Expr) -> T;
+    fn visit_nil_coalesce_assign_expr(&mut self, target: &Expr, value: &Expr) -> T;
+    fn visit_compound_assign_expr(
+        &mut self,
+        target: &Expr,
+        operator: &Token,
+        op: &BinaryOp,
+        value: &Expr,
+        postfix: bool,
+    ) -> T;
+    fn visit_destructure_assign_expr(&mut self, target: &Expr, equals: &Token, value: &Expr) -> T;
+    fn visit_list_expr(&mut self, elements: &[Expr]) -> T;
+    fn visit_tuple_expr(&mut self, elements: &[Expr]) -> T;
+    fn visit_map_expr(&mut self, brace: &Token, entries: &[(Expr, Expr)]) -> T;
+    fn visit_range_expr(
+        &mut self,
+        start: &Expr,
+        operator: &Token,
+        end: &Expr,
+        inclusive: bool,
+    ) -> T;
+    fn visit_index_expr(&mut self, object: &Expr, bracket: &Token, index: &Expr) -> T;
+    fn visit_index_set_expr(
+        &mut self,
+        object: &Expr,
+        bracket: &Token,
+        index: &Expr,
+        value: &Expr,
+    ) -> T;
+    fn visit_this_expr(&mut self, keyword: &VarRef) -> T;
+    fn visit_super_expr(&mut self, keyword: &VarRef, method: &Token) -> T;
 }
 
 impl Expr {
     pub fn accept<T>(&self, visitor: &mut dyn ExprVisitor<T>) -> T {
         match self {
-            Expr::Binary(left, op, right) => visitor.visit_binary_expr(left, op, right),
-            Expr::Unary(op, expr) => visitor.visit_unary_expr(op, expr),
+            Expr::Binary(left, operator, op, right) => {
+                visitor.visit_binary_expr(left, operator, op, right)
+            }
+            Expr::Logical(left, op, right) => visitor.visit_logical_expr(left, op, right),
+            Expr::Conditional(condition, then_branch, else_branch) => {
+                visitor.visit_conditional_expr(condition, then_branch, else_branch)
+            }
+            Expr::Comma(left, right) => visitor.visit_comma_expr(left, right),
+            Expr::Unary(operator, op, expr) => visitor.visit_unary_expr(operator, op, expr),
             Expr::Grouping(expr) => visitor.visit_grouping_expr(expr),
             Expr::Literal(value) => visitor.visit_literal_expr(value),
-            Expr::Variable(id) => visitor.visit_variable_expr(id),
-            Expr::Assign(id, expr) => visitor.visit_assignment_expr(id, expr),
+            Expr::Interpolation(parts) => visitor.visit_interpolation_expr(parts),
+            Expr::Variable(var) => visitor.visit_variable_expr(var),
+            Expr::Assign(var, expr) => visitor.visit_assignment_expr(var, expr),
+            Expr::Call(callee, paren, args) => visitor.visit_call_expr(callee, paren, args),
+            Expr::Get(object, name) => visitor.visit_get_expr(object, name),
+            Expr::Set(object, name, value) => visitor.visit_set_expr(object, name, value),
+            Expr::NilCoalesceAssign(target, value) => {
+                visitor.visit_nil_coalesce_assign_expr(target, value)
+            }
+            Expr::CompoundAssign(target, operator, op, value, postfix) => {
+                visitor.visit_compound_assign_expr(target, operator, op, value, *postfix)
+            }
+            Expr::DestructureAssign(target, equals, value) => {
+                visitor.visit_destructure_assign_expr(target, equals, value)
+            }
+            Expr::List(elements) => visitor.visit_list_expr(elements),
+            Expr::Tuple(elements) => visitor.visit_tuple_expr(elements),
+            Expr::Map(brace, entries) => visitor.visit_map_expr(brace, entries),
+            Expr::Range(start, operator, end, inclusive) => {
+                visitor.visit_range_expr(start, operator, end, *inclusive)
+            }
+            Expr::Index(object, bracket, index) => visitor.visit_index_expr(object, bracket, index),
+            Expr::IndexSet(object, bracket, index, value) => {
+                visitor.visit_index_set_expr(object, bracket, index, value)
+            }
+            Expr::This(keyword) => visitor.visit_this_expr(keyword),
+            Expr::Super(keyword, method) => visitor.visit_super_expr(keyword, method),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct FunctionDecl {
+    pub name: Token,
+    pub params: Vec<Token>,
+    pub body: Vec<Stmt>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ClassDecl {
+    pub name: Token,
+    pub superclass: Option<VarRef>,
+    pub methods: Vec<Rc<FunctionDecl>>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Pattern {
+    Literal(LiteralValue),
+    /// Number range, the boolean flag is set when the upper bound is inclusive.
+    Range(LiteralValue, LiteralValue, bool),
+    Binding(Token),
+    Wildcard,
+}
+
+/// Names bound by a destructuring declaration.
+#[derive(Debug, PartialEq)]
+pub enum BindingPattern {
+    Name(Token),
+    Tuple(Vec<BindingPattern>),
+}
+
+impl BindingPattern {
+    /// Names bound by the pattern, from left to right.
+    pub fn names(&self) -> Vec<&Token> {
+        match self {
+            BindingPattern::Name(name) => vec![name],
+            BindingPattern::Tuple(elements) => elements.iter().flat_map(Self::names).collect(),
         }
     }
 }
 
+#[derive(Debug, PartialEq)]
+pub struct MatchArm {
+    pub pattern: Pattern,
+    pub guard: Option<Expr>,
+    pub body: Stmt,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct CatchClause {
+    pub name: Token,
+    pub body: Vec<Stmt>,
+}
+
 #[derive(Debug, PartialEq)]
 pub enum Stmt {
     Expression(Box<Expr>),
     Var(Token, Option<Box<Expr>>),
+    // Pattern, '=' token and initializer.
+    VarDestructure(BindingPattern, Token, Box<Expr>),
+    Const(Token, Box<Expr>),
     Print(Box<Expr>),
     Block(Vec<Stmt>),
+    If(Box<Expr>, Box<Stmt>, Option<Box<Stmt>>),
+    While(Box<Expr>, Box<Stmt>, Option<Box<Expr>>),
+    // Loop variable, iterated value and body.
+    ForIn(Token, Box<Expr>, Box<Stmt>),
+    Break,
+    Continue,
+    Function(Rc<FunctionDecl>),
+    Return(Token, Option<Box<Expr>>),
+    Match(Token, Box<Expr>, Vec<MatchArm>),
+    Class(ClassDecl),
+    Throw(Token, Box<Expr>),
+    // Keyword, path of the module and alias binding it.
+    Import(Token, String, Token),
+    // At least one of the catch clause and the finally block is present.
+    Try(Vec<Stmt>, Option<CatchClause>, Option<Vec<Stmt>>),
 }
 
 pub trait StmtVisitor<T> {
     fn visit_expression_stmt(&mut self, expr: &Expr) -> T;
     fn visit_var_stmt(&mut self, id: &Token, initalizer: &Option<Box<Expr>>) -> T;
+    fn visit_var_destructure_stmt(
+        &mut self,
+        pattern: &BindingPattern,
+        equals: &Token,
+        initializer: &Expr,
+    ) -> T;
+    fn visit_const_stmt(&mut self, id: &Token, initializer: &Expr) -> T;
     fn visit_print_stmt(&mut self, expr: &Expr) -> T;
     fn visit_block_stmt(&mut self, body: &[Stmt]) -> T;
+    fn visit_if_stmt(
+        &mut self,
+        condition: &Expr,
+        then_branch: &Stmt,
+        else_branch: &Option<Box<Stmt>>,
+    ) -> T;
+    fn visit_while_stmt(
+        &mut self,
+        condition: &Expr,
+        body: &Stmt,
+        increment: &Option<Box<Expr>>,
+    ) -> T;
+    fn visit_for_in_stmt(&mut self, name: &Token, iterable: &Expr, body: &Stmt) -> T;
+    fn visit_break_stmt(&mut self) -> T;
+    fn visit_continue_stmt(&mut self) -> T;
+    fn visit_function_stmt(&mut self, declaration: &Rc<FunctionDecl>) -> T;
+    fn visit_return_stmt(&mut self, keyword: &Token, value: &Option<Box<Expr>>) -> T;
+    fn visit_match_stmt(&mut self, keyword: &Token, subject: &Expr, arms: &[MatchArm]) -> T;
+    fn visit_class_stmt(&mut self, declaration: &ClassDecl) -> T;
+    fn visit_throw_stmt(&mut self, keyword: &Token, value: &Expr) -> T;
+    fn visit_import_stmt(&mut self, keyword: &Token, path: &str, alias: &Token) -> T;
+    fn visit_try_stmt(
+        &mut self,
+        body: &[Stmt],
+        catch: &Option<CatchClause>,
+        finally: &Option<Vec<Stmt>>,
+    ) -> T;
 }
 
 impl Stmt {
@@ -137,8 +427,28 @@ impl Stmt {
         match self {
             Stmt::Expression(expr) => visitor.visit_expression_stmt(expr),
             Stmt::Var(id, initalizer) => visitor.visit_var_stmt(id, initalizer),
+            Stmt::VarDestructure(pattern, equals, initializer) => {
+                visitor.visit_var_destructure_stmt(pattern, equals, initializer)
+            }
+            Stmt::Const(id, initializer) => visitor.visit_const_stmt(id, initializer),
             Stmt::Print(expr) => visitor.visit_print_stmt(expr),
             Stmt::Block(body) => visitor.visit_block_stmt(body),
+            Stmt::If(condition, then_branch, else_branch) => {
+                visitor.visit_if_stmt(condition, then_branch, else_branch)
+            }
+            Stmt::While(condition, body, increment) => {
+                visitor.visit_while_stmt(condition, body, increment)
+            }
+            Stmt::ForIn(name, iterable, body) => visitor.visit_for_in_stmt(name, iterable, body),
+            Stmt::Break => visitor.visit_break_stmt(),
+            Stmt::Continue => visitor.visit_continue_stmt(),
+            Stmt::Function(declaration) => visitor.visit_function_stmt(declaration),
+            Stmt::Return(keyword, value) => visitor.visit_return_stmt(keyword, value),
+            Stmt::Match(keyword, subject, arms) => visitor.visit_match_stmt(keyword, subject, arms),
+            Stmt::Class(declaration) => visitor.visit_class_stmt(declaration),
+            Stmt::Throw(keyword, value) => visitor.visit_throw_stmt(keyword, value),
+            Stmt::Import(keyword, path, alias) => visitor.visit_import_stmt(keyword, path, alias),
+            Stmt::Try(body, catch, finally) => visitor.visit_try_stmt(body, catch, finally),
         }
     }
 }
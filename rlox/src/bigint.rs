@@ -0,0 +1,415 @@
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::fmt;
+use std::ops::{Add, Mul, Neg, Sub};
+
+/// Arbitrary-precision integer, stored as a sign and a magnitude of 32 bits digits, least
+/// significant first. The magnitude has no leading zero digit and zero is never negative, so that
+/// equal values share the same representation.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BigInt {
+    negative: bool,
+    digits: Vec<u32>,
+}
+
+impl BigInt {
+    pub fn zero() -> Self {
+        BigInt::from_parts(false, vec![])
+    }
+
+    pub fn one() -> Self {
+        BigInt::from_parts(false, vec![1])
+    }
+
+    fn from_parts(negative: bool, mut digits: Vec<u32>) -> Self {
+        while digits.last() == Some(&0) {
+            digits.pop();
+        }
+
+        BigInt {
+            negative: negative && !digits.is_empty(),
+            digits,
+        }
+    }
+
+    /// Parses digits in the radix, without sign nor separators.
+    pub fn parse(digits: &str, radix: u32) -> Option<Self> {
+        if digits.is_empty() {
+            return None;
+        }
+
+        let mut magnitude = vec![];
+        for c in digits.chars() {
+            mul_small_add(&mut magnitude, radix, c.to_digit(radix)?);
+        }
+
+        Some(BigInt::from_parts(false, magnitude))
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.digits.is_empty()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    pub fn abs(&self) -> BigInt {
+        BigInt::from_parts(false, self.digits.clone())
+    }
+
+    /// Number of bits of the magnitude, zero for zero.
+    pub fn bits(&self) -> u64 {
+        match self.digits.last() {
+            Some(top) => self.digits.len() as u64 * 32 - top.leading_zeros() as u64,
+            None => 0,
+        }
+    }
+
+    pub fn to_i64(&self) -> Option<i64> {
+        if self.digits.len() > 2 {
+            return None;
+        }
+
+        let magnitude = self
+            .digits
+            .iter()
+            .rev()
+            .fold(0u64, |acc, digit| acc << 32 | *digit as u64);
+
+        if self.negative {
+            // The magnitude of i64::MIN doesn't fit in an i64, it wraps to itself.
+            (magnitude <= 1 << 63).then(|| (magnitude as i64).wrapping_neg())
+        } else {
+            i64::try_from(magnitude).ok()
+        }
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        let magnitude = self
+            .digits
+            .iter()
+            .rev()
+            .fold(0.0, |acc, digit| acc * 4294967296.0 + *digit as f64);
+
+        if self.negative {
+            -magnitude
+        } else {
+            magnitude
+        }
+    }
+
+    /// Multiplies by 2 to the power of `bits`.
+    pub fn shl(&self, bits: u32) -> BigInt {
+        let mut digits = vec![0; (bits / 32) as usize];
+        let shift = bits % 32;
+
+        let mut carry = 0;
+        for digit in &self.digits {
+            let shifted = (*digit as u64) << shift | carry;
+            digits.push(shifted as u32);
+            carry = shifted >> 32;
+        }
+        digits.push(carry as u32);
+
+        BigInt::from_parts(self.negative, digits)
+    }
+
+    /// Divides, truncating the quotient toward zero. The remainder has the sign of the dividend.
+    ///
+    /// Panics when the divisor is zero.
+    pub fn div_rem(&self, other: &BigInt) -> (BigInt, BigInt) {
+        assert!(!other.is_zero(), "division by zero");
+
+        let (quotient, remainder) = div_rem_magnitude(&self.digits, &other.digits);
+        (
+            BigInt::from_parts(self.negative != other.negative, quotient),
+            BigInt::from_parts(self.negative, remainder),
+        )
+    }
+
+    /// Greatest common divisor, always positive unless both values are zero.
+    pub fn gcd(&self, other: &BigInt) -> BigInt {
+        let (mut a, mut b) = (self.abs(), other.abs());
+        while !b.is_zero() {
+            let (_, remainder) = a.div_rem(&b);
+            a = b;
+            b = remainder;
+        }
+
+        a
+    }
+}
+
+impl From<i64> for BigInt {
+    fn from(value: i64) -> Self {
+        BigInt::from_parts(value < 0, u64_digits(value.unsigned_abs()))
+    }
+}
+
+impl From<u64> for BigInt {
+    fn from(value: u64) -> Self {
+        BigInt::from_parts(false, u64_digits(value))
+    }
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => cmp_magnitude(&self.digits, &other.digits),
+            (true, true) => cmp_magnitude(&other.digits, &self.digits),
+        }
+    }
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Add for &BigInt {
+    type Output = BigInt;
+
+    fn add(self, other: &BigInt) -> BigInt {
+        if self.negative == other.negative {
+            return BigInt::from_parts(self.negative, add_magnitude(&self.digits, &other.digits));
+        }
+
+        match cmp_magnitude(&self.digits, &other.digits) {
+            Ordering::Less => {
+                BigInt::from_parts(other.negative, sub_magnitude(&other.digits, &self.digits))
+            }
+            _ => BigInt::from_parts(self.negative, sub_magnitude(&self.digits, &other.digits)),
+        }
+    }
+}
+
+impl Sub for &BigInt {
+    type Output = BigInt;
+
+    fn sub(self, other: &BigInt) -> BigInt {
+        self + &-other
+    }
+}
+
+impl Mul for &BigInt {
+    type Output = BigInt;
+
+    fn mul(self, other: &BigInt) -> BigInt {
+        BigInt::from_parts(
+            self.negative != other.negative,
+            mul_magnitude(&self.digits, &other.digits),
+        )
+    }
+}
+
+impl Neg for &BigInt {
+    type Output = BigInt;
+
+    fn neg(self) -> BigInt {
+        BigInt::from_parts(!self.negative, self.digits.clone())
+    }
+}
+
+impl fmt::Display for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_zero() {
+            return write!(f, "0");
+        }
+
+        // Splits the magnitude in groups of 9 decimal digits, least significant first.
+        let mut groups = vec![];
+        let mut magnitude = self.digits.clone();
+        while !magnitude.is_empty() {
+            let (quotient, remainder) = div_rem_small(&magnitude, 1_000_000_000);
+            groups.push(remainder);
+            magnitude = quotient;
+            while magnitude.last() == Some(&0) {
+                magnitude.pop();
+            }
+        }
+
+        if self.negative {
+            write!(f, "-")?;
+        }
+        let mut groups = groups.iter().rev();
+        if let Some(group) = groups.next() {
+            write!(f, "{}", group)?;
+        }
+        for group in groups {
+            write!(f, "{:09}", group)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn u64_digits(value: u64) -> Vec<u32> {
+    vec![value as u32, (value >> 32) as u32]
+}
+
+fn cmp_magnitude(a: &[u32], b: &[u32]) -> Ordering {
+    a.len()
+        .cmp(&b.len())
+        .then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+fn add_magnitude(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut result = Vec::with_capacity(a.len().max(b.len()) + 1);
+    let mut carry = 0;
+    for i in 0..a.len().max(b.len()) {
+        let sum = *a.get(i).unwrap_or(&0) as u64 + *b.get(i).unwrap_or(&0) as u64 + carry;
+        result.push(sum as u32);
+        carry = sum >> 32;
+    }
+    result.push(carry as u32);
+
+    result
+}
+
+/// Subtracts the magnitudes, `a` must be greater or equal to `b`.
+fn sub_magnitude(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut result = Vec::with_capacity(a.len());
+    let mut borrow = 0;
+    for (i, digit) in a.iter().enumerate() {
+        let difference = *digit as i64 - *b.get(i).unwrap_or(&0) as i64 - borrow;
+        result.push(difference.rem_euclid(1 << 32) as u32);
+        borrow = (difference < 0) as i64;
+    }
+
+    result
+}
+
+fn mul_magnitude(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut result = vec![0; a.len() + b.len()];
+    for (i, x) in a.iter().enumerate() {
+        let mut carry = 0;
+        for (j, y) in b.iter().enumerate() {
+            let product = result[i + j] as u64 + *x as u64 * *y as u64 + carry;
+            result[i + j] = product as u32;
+            carry = product >> 32;
+        }
+        result[i + b.len()] = carry as u32;
+    }
+
+    result
+}
+
+fn mul_small_add(magnitude: &mut Vec<u32>, factor: u32, addend: u32) {
+    let mut carry = addend as u64;
+    for digit in magnitude.iter_mut() {
+        let product = *digit as u64 * factor as u64 + carry;
+        *digit = product as u32;
+        carry = product >> 32;
+    }
+
+    if carry > 0 {
+        magnitude.push(carry as u32);
+    }
+}
+
+fn div_rem_small(a: &[u32], divisor: u32) -> (Vec<u32>, u32) {
+    let mut quotient = vec![0; a.len()];
+    let mut remainder = 0u64;
+    for (i, digit) in a.iter().enumerate().rev() {
+        let current = remainder << 32 | *digit as u64;
+        quotient[i] = (current / divisor as u64) as u32;
+        remainder = current % divisor as u64;
+    }
+
+    (quotient, remainder as u32)
+}
+
+/// Long division one digit at a time, as in Knuth's algorithm D. The operands are scaled so that
+/// the top digit of the divisor has its high bit set, which makes the estimate of each quotient
+/// digit from the top digits of the operands at most two too large.
+fn div_rem_magnitude(a: &[u32], b: &[u32]) -> (Vec<u32>, Vec<u32>) {
+    if let [divisor] = b {
+        let (quotient, remainder) = div_rem_small(a, *divisor);
+        return (quotient, vec![remainder]);
+    }
+    if a.len() < b.len() {
+        return (vec![], a.to_vec());
+    }
+
+    let n = b.len();
+    let shift = b[n - 1].leading_zeros();
+    let mut divisor = shl_digits(b, shift);
+    divisor.truncate(n);
+    let mut remainder = shl_digits(a, shift);
+
+    let (top, next) = (divisor[n - 1] as u64, divisor[n - 2] as u64);
+    let mut quotient = vec![0; a.len() - n + 1];
+    for j in (0..quotient.len()).rev() {
+        let current = (remainder[j + n] as u64) << 32 | remainder[j + n - 1] as u64;
+        let mut estimate = current / top;
+        let mut rest = current % top;
+        while estimate >> 32 != 0 || estimate * next > (rest << 32 | remainder[j + n - 2] as u64) {
+            estimate -= 1;
+            rest += top;
+            if rest >> 32 != 0 {
+                break;
+            }
+        }
+
+        // remainder -= estimate * divisor, shifted by j digits.
+        let mut borrow = 0;
+        let mut carry = 0;
+        for (i, digit) in divisor.iter().enumerate() {
+            let product = estimate * *digit as u64 + carry;
+            carry = product >> 32;
+            let difference = remainder[i + j] as i64 - borrow - (product as u32) as i64;
+            remainder[i + j] = difference as u32;
+            borrow = (difference < 0) as i64;
+        }
+        let difference = remainder[j + n] as i64 - borrow - carry as i64;
+        remainder[j + n] = difference as u32;
+
+        // The estimate was one too large, add the divisor back.
+        if difference < 0 {
+            estimate -= 1;
+            let mut carry = 0;
+            for (i, digit) in divisor.iter().enumerate() {
+                let sum = remainder[i + j] as u64 + *digit as u64 + carry;
+                remainder[i + j] = sum as u32;
+                carry = sum >> 32;
+            }
+            remainder[j + n] = remainder[j + n].wrapping_add(carry as u32);
+        }
+
+        quotient[j] = estimate as u32;
+    }
+
+    remainder.truncate(n);
+    (quotient, shr_digits(&remainder, shift))
+}
+
+/// Shifts the magnitude left by less than a digit, the result has an extra digit for the carry.
+fn shl_digits(magnitude: &[u32], shift: u32) -> Vec<u32> {
+    let mut result = Vec::with_capacity(magnitude.len() + 1);
+    let mut carry = 0;
+    for digit in magnitude {
+        let shifted = (*digit as u64) << shift | carry;
+        result.push(shifted as u32);
+        carry = shifted >> 32;
+    }
+    result.push(carry as u32);
+
+    result
+}
+
+/// Shifts the magnitude right by less than a digit.
+fn shr_digits(magnitude: &[u32], shift: u32) -> Vec<u32> {
+    let mut result = vec![0; magnitude.len()];
+    let mut carry = 0;
+    for (i, digit) in magnitude.iter().enumerate().rev() {
+        let shifted = ((carry as u64) << 32 | *digit as u64) >> shift;
+        result[i] = shifted as u32;
+        carry = *digit & ((1u64 << shift) - 1) as u32;
+    }
+
+    result
+}
@@ -6,10 +6,16 @@ use crate::ast::Token;
 use crate::object::Object;
 use crate::result::{Error, RloxResult};
 
+#[derive(Debug)]
+struct Binding {
+    value: Object,
+    mutable: bool,
+}
+
 #[derive(Debug)]
 pub struct Environment {
     enclosing: Option<Rc<Environment>>,
-    values: RefCell<HashMap<String, Object>>,
+    values: RefCell<HashMap<String, Binding>>,
 }
 
 impl Environment {
@@ -29,13 +35,83 @@ impl Environment {
         })
     }
 
-    pub fn define(&self, id: &Token, value: Object) {
-        self.values.borrow_mut().insert(id.lexeme.to_owned(), value);
+    pub fn define(&self, name: &str, value: Object) {
+        self.insert(name, value, true);
+    }
+
+    pub fn define_const(&self, name: &str, value: Object) {
+        self.insert(name, value, false);
+    }
+
+    fn insert(&self, name: &str, value: Object, mutable: bool) {
+        self.values
+            .borrow_mut()
+            .insert(name.to_owned(), Binding { value, mutable });
+    }
+
+    /// Reads a variable resolved to the environment at the distance. The binding must be there, a
+    /// miss means that the resolver and the interpreter disagree on the scopes.
+    pub fn get_at(&self, distance: usize, id: &Token) -> Object {
+        self.ancestor(distance)
+            .get_local(&id.lexeme)
+            .expect("Resolved variable should be defined.")
+    }
+
+    pub fn assign_at(&self, distance: usize, id: &Token, value: Object) -> RloxResult<Object> {
+        let mut values = self.ancestor(distance).values.borrow_mut();
+        let binding = values
+            .get_mut(&id.lexeme)
+            .expect("Resolved variable should be defined.");
+
+        Self::assign_binding(binding, id, value)
+    }
+
+    fn assign_binding(binding: &mut Binding, id: &Token, value: Object) -> RloxResult<Object> {
+        if !binding.mutable {
+            return Err(Error::Runtime(
+                id.line,
+                format!("Cannot assign to constant '{}'.", id.lexeme),
+            ));
+        }
+
+        binding.value = value.to_owned();
+        Ok(value)
+    }
+
+    /// Reads a binding defined by the interpreter itself rather than by a declaration, such as
+    /// `this` in bound methods.
+    pub fn get_internal_at(&self, distance: usize, name: &str) -> Object {
+        self.ancestor(distance)
+            .values
+            .borrow()
+            .get(name)
+            .map(|binding| binding.value.clone())
+            .expect("Internal binding should be defined.")
+    }
+
+    /// Reads a binding of this environment only, ignoring the enclosing ones.
+    pub fn get_local(&self, name: &str) -> Option<Object> {
+        self.values
+            .borrow()
+            .get(name)
+            .map(|binding| binding.value.clone())
+    }
+
+    fn ancestor(&self, distance: usize) -> &Environment {
+        let mut env = self;
+        for _ in 0..distance {
+            env = env
+                .enclosing
+                .as_deref()
+                .expect("Resolved distance exceeds environment depth.");
+        }
+
+        env
     }
 
     pub fn get(&self, id: &Token) -> RloxResult<Object> {
-        if let Some(val) = self.values.borrow().get(&id.lexeme) {
-            return Ok((*val).clone());
+        if let Some(binding) = self.values.borrow().get(&id.lexeme) {
+            return Ok(binding.value.clone());
         }
 
         match &self.enclosing {
@@ -50,9 +126,8 @@ impl Environment {
     pub fn assign(&self, id: &Token, value: Object) -> RloxResult<Object> {
         let mut values = self.values.borrow_mut();
 
-        if values.get(&id.lexeme).is_some() {
-            values.insert(id.lexeme.to_owned(), value.to_owned());
-            return Ok(value);
+        if let Some(binding) = values.get_mut(&id.lexeme) {
+            return Self::assign_binding(binding, id, value);
         }
 
         match &self.enclosing {
@@ -1,23 +1,135 @@
+use std::cell::RefCell;
 use std::cmp;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fs;
 use std::io;
+use std::mem;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 
-use crate::ast::{BinaryOp, Expr, ExprVisitor, LiteralValue, Stmt, StmtVisitor, Token, UnaryOp};
+use crate::ast::{
+    BinaryOp, BindingPattern, CatchClause, ClassDecl, Expr, ExprVisitor, FunctionDecl,
+    LiteralValue, LogicalOp, MatchArm, Pattern, Stmt, StmtVisitor, Token, UnaryOp, VarRef,
+};
+use crate::bigint::BigInt;
 use crate::environment::Environment;
-use crate::object::Object;
+use crate::natives;
+use crate::object::{Class, Function, Instance, Map, Module, Native, Object, Range};
+use crate::parser::Parser;
+use crate::rational::Rational;
+use crate::resolver::Resolver;
 use crate::result::{Error, RloxResult};
+use crate::scanner::Scanner;
+
+/// Storage location designated by an assignment target, with the object holding it evaluated.
+enum Place<'a> {
+    Variable(&'a VarRef),
+    Property(Rc<Instance>, &'a Token),
+    Index(Object, &'a Token, Object),
+}
+
+/// Values left to produce in a `for-in` loop.
+enum Iteration {
+    // Characters of a string and keys of a map, collected when the loop starts.
+    Values(std::vec::IntoIter<Object>),
+    // Next integer and the excluded end, wide enough for inclusive ranges ending at `i64::MAX`.
+    Range(i128, i128),
+    // Elements are read as the loop goes, it sees the changes made to the list.
+    List(Rc<RefCell<Vec<Object>>>, usize),
+    // Bound `next` method, the iteration ends when it returns nil.
+    Iterator(Function),
+}
+
+/// Maximum number of nested calls. Deeper recursion is reported as a runtime error rather than
+/// overflowing the native stack, which holds a few frames per call.
+const MAX_CALL_DEPTH: usize = 1024;
+
+/// Native stack size needed to reach the maximum call depth, with room for the nested
+/// expressions and statements of each call in debug builds. The main thread is usually smaller,
+/// so programs should be interpreted on a thread spawned with this stack size.
+pub const STACK_SIZE: usize = 64 * 1024 * 1024;
+
+/// Maximum size in bits of the integers built by powers and left shifts, so that a large exponent
+/// is reported as an error instead of exhausting the memory.
+const MAX_INTEGER_BITS: u64 = 1 << 16;
+
+/// Outcome of a statement execution. Loop and function control flow unwinds through it rather
+/// than through `Error`, so that enclosing blocks can restore their environment on the way out.
+enum Completion {
+    Normal,
+    Break,
+    Continue,
+    Return(Object),
+}
 
 pub struct Interpreter<W: io::Write> {
     out: W,
+    // Natives and built-in classes, enclosing the globals of every module.
+    builtins: Rc<Environment>,
+    globals: Rc<Environment>,
     environment: Rc<Environment>,
+    // Class of the values caught for runtime errors, kept even if the global is shadowed.
+    error_class: Rc<Class>,
+    // Path of the file being run, imports are resolved relative to its directory.
+    path: Option<PathBuf>,
+    // Canonical paths of the files being run, from the main one to the innermost import.
+    running: Vec<PathBuf>,
+    modules: HashMap<PathBuf, Rc<Module>>,
+    call_depth: usize,
 }
 
 impl<W: io::Write> Interpreter<W> {
     pub fn new(out: W) -> Self {
-        Self {
-            environment: Environment::new(),
+        let builtins = Environment::new();
+        let globals = Environment::from(&builtins);
+
+        let error_class = Rc::new(Class {
+            name: String::from("Error"),
+            superclass: None,
+            methods: HashMap::new(),
+        });
+        builtins.define("Error", Object::Class(Rc::clone(&error_class)));
+
+        let mut interpreter = Self {
+            environment: Rc::clone(&globals),
+            builtins,
+            globals,
             out,
+            error_class,
+            path: None,
+            running: vec![],
+            modules: HashMap::new(),
+            call_depth: 0,
+        };
+
+        for (name, arity, function) in natives::BUILTINS {
+            interpreter.define_native(name, *arity, function);
         }
+
+        interpreter
+    }
+
+    /// Registers a Rust function in the global environment, so that it can be called from Lox
+    /// with the given number of arguments.
+    pub fn define_native<F>(&mut self, name: &str, arity: usize, function: F)
+    where
+        F: Fn(&[Object]) -> Result<Object, String> + 'static,
+    {
+        let native = Native {
+            name: name.to_owned(),
+            arity,
+            function: Box::new(function),
+        };
+
+        self.builtins.define(name, Object::Native(Rc::new(native)));
+    }
+
+    /// Sets the path of the file being run, so that its imports are resolved relative to it.
+    pub fn set_path<P: Into<PathBuf>>(&mut self, path: P) {
+        let path = path.into();
+        self.running = fs::canonicalize(&path).into_iter().collect();
+        self.path = Some(path);
     }
 
     pub fn interpret(&mut self, statements: &[Stmt]) -> RloxResult<()> {
@@ -28,62 +140,723 @@ impl<W: io::Write> Interpreter<W> {
         Ok(())
     }
 
-    fn execute(&mut self, statement: &Stmt) -> RloxResult<()> {
+    fn execute(&mut self, statement: &Stmt) -> RloxResult<Completion> {
         statement.accept(self)
     }
 
-    fn execute_block(&mut self, body: &[Stmt], block_env: Rc<Environment>) -> RloxResult<()> {
+    fn execute_block(
+        &mut self,
+        body: &[Stmt],
+        block_env: Rc<Environment>,
+    ) -> RloxResult<Completion> {
         let current_env = self.environment.clone();
         self.environment = block_env;
 
         for stmt in body {
-            if let Err(err) = self.execute(stmt) {
-                self.environment = current_env;
-                return Err(err);
+            match self.execute(stmt) {
+                Ok(Completion::Normal) => {}
+                completion => {
+                    self.environment = current_env;
+                    return completion;
+                }
             }
         }
 
         self.environment = current_env;
-        Ok(())
+        Ok(Completion::Normal)
     }
 
     fn evaluate(&mut self, expr: &Expr) -> RloxResult<Object> {
         expr.accept(self)
     }
 
-    fn err(&self, msg: &str) -> Error {
-        Error::Runtime(0, String::from(msg))
+    /// Value bound by a `catch` clause for the error, `None` if it can't be caught. Runtime
+    /// errors are caught as `Error` instances holding their message and line.
+    fn exception_value(&self, err: &Error) -> Option<Object> {
+        match err {
+            Error::Runtime(line, msg) => {
+                let instance = Instance::new(Rc::clone(&self.error_class));
+                {
+                    let mut fields = instance.fields.borrow_mut();
+                    fields.insert(String::from("message"), Object::String(msg.clone()));
+                    fields.insert(String::from("line"), Object::Integer(*line as i64));
+                }
+
+                Some(Object::Instance(Rc::new(instance)))
+            }
+            Error::Thrown(_, value) => Some(value.clone()),
+            _ => None,
+        }
     }
-}
 
-impl<W: io::Write> ExprVisitor<RloxResult<Object>> for Interpreter<W> {
-    fn visit_binary_expr(
+    fn call_function(
         &mut self,
-        left: &Expr,
-        op: &BinaryOp,
-        right: &Expr,
+        paren: &Token,
+        function: &Function,
+        args: Vec<Object>,
     ) -> RloxResult<Object> {
-        let left = self.evaluate(left)?;
-        let right = self.evaluate(right)?;
+        if self.call_depth == MAX_CALL_DEPTH {
+            return Err(Error::Runtime(paren.line, String::from("Stack overflow.")));
+        }
 
-        match op {
-            BinaryOp::Plus => match (left, right) {
-                (Object::Number(a), Object::Number(b)) => Ok(Object::Number(a + b)),
-                (Object::String(a), Object::String(b)) => Ok(Object::String(format!("{}{}", a, b))),
-                (_, _) => Err(self.err("Operands must be numbers or strings.")),
+        let env = Environment::from(&function.closure);
+        for (param, arg) in function.declaration.params.iter().zip(args) {
+            env.define(&param.lexeme, arg);
+        }
+
+        let previous_globals = mem::replace(&mut self.globals, Rc::clone(&function.globals));
+        self.call_depth += 1;
+        let completion = self.execute_block(&function.declaration.body, env);
+        self.call_depth -= 1;
+        self.globals = previous_globals;
+        let completion = completion?;
+
+        // Initializers always return the instance, including on early returns.
+        if function.is_initializer {
+            return Ok(function.closure.get_internal_at(0, "this"));
+        }
+
+        match completion {
+            Completion::Return(value) => Ok(value),
+            _ => Ok(Object::Nil),
+        }
+    }
+
+    /// Runs the module at the path, relative to the current file, or returns it from the cache if
+    /// it already ran.
+    fn import(&mut self, keyword: &Token, path: &str) -> RloxResult<Rc<Module>> {
+        let importer = match &self.path {
+            Some(importer) => importer.display().to_string(),
+            None => String::from("<script>"),
+        };
+        let import_err = |msg: String| Error::Import(importer.clone(), keyword.line, msg);
+
+        let path = match self.path.as_deref().and_then(Path::parent) {
+            Some(dir) => dir.join(path),
+            None => PathBuf::from(path),
+        };
+        let canonical = fs::canonicalize(&path)
+            .map_err(|_| import_err(format!("Module '{}' not found.", path.display())))?;
+
+        if let Some(module) = self.modules.get(&canonical) {
+            return Ok(Rc::clone(module));
+        }
+        if self.running.contains(&canonical) {
+            return Err(import_err(format!(
+                "Import cycle through module '{}'.",
+                path.display()
+            )));
+        }
+
+        let source = fs::read_to_string(&canonical).map_err(|err| {
+            import_err(format!("Can't read module '{}': {}.", path.display(), err))
+        })?;
+        // Errors of the module are reported at the import, each one on its own line.
+        let module_err = |errors: Vec<Error>| {
+            let errors: Vec<_> = errors.iter().map(Error::to_string).collect();
+            import_err(format!(
+                "Errors in module '{}':\n{}",
+                path.display(),
+                errors.join("\n")
+            ))
+        };
+        let tokens = Scanner::scan(&source).map_err(|e| module_err(vec![e]))?;
+        let (statements, _) = Parser::parse(tokens).map_err(module_err)?;
+        Resolver::resolve(&statements).map_err(module_err)?;
+
+        let module = Rc::new(Module {
+            path: path.display().to_string(),
+            globals: Environment::from(&self.builtins),
+        });
+
+        // The module runs as a file of its own, the importing one resumes once it's done.
+        let previous_globals = mem::replace(&mut self.globals, Rc::clone(&module.globals));
+        let previous_env = mem::replace(&mut self.environment, Rc::clone(&module.globals));
+        let previous_path = self.path.replace(path);
+        self.running.push(canonical.clone());
+
+        let result = self.interpret(&statements);
+
+        self.running.pop();
+        self.path = previous_path;
+        self.environment = previous_env;
+        self.globals = previous_globals;
+        result?;
+
+        self.modules.insert(canonical, Rc::clone(&module));
+        Ok(module)
+    }
+
+    fn call_class(
+        &mut self,
+        paren: &Token,
+        class: Rc<Class>,
+        args: Vec<Object>,
+    ) -> RloxResult<Object> {
+        let instance = Rc::new(Instance::new(Rc::clone(&class)));
+
+        if let Some(initializer) = class.find_method("init") {
+            self.call_function(paren, &initializer.bind(Rc::clone(&instance)), args)?;
+        }
+
+        Ok(Object::Instance(instance))
+    }
+
+    fn assign_variable(&mut self, var: &VarRef, value: Object) -> RloxResult<Object> {
+        match var.depth.get() {
+            Some(depth) => self.environment.assign_at(depth, &var.id, value),
+            None => self.globals.assign(&var.id, value),
+        }
+    }
+
+    /// Evaluates the sub-expressions of an assignment target, so that it can be read and
+    /// written without evaluating them again.
+    fn evaluate_place<'a>(&mut self, target: &'a Expr) -> RloxResult<Place<'a>> {
+        match target {
+            Expr::Variable(var) => Ok(Place::Variable(var)),
+            Expr::Get(object, name) => {
+                let instance =
+                    self.evaluate_instance(object, name, "Only instances have fields.")?;
+                Ok(Place::Property(instance, name))
+            }
+            Expr::Index(object, bracket, index) => {
+                let collection = self.evaluate(object)?;
+                let index = self.evaluate(index)?;
+                Ok(Place::Index(collection, bracket, index))
+            }
+            _ => unreachable!("Invalid assignment targets are rejected by the parser."),
+        }
+    }
+
+    fn read_place(&mut self, place: &Place) -> RloxResult<Object> {
+        match place {
+            Place::Variable(var) => self.visit_variable_expr(var),
+            Place::Property(instance, name) => Self::get_property(instance, name),
+            Place::Index(collection, bracket, index) => Self::get_index(bracket, collection, index),
+        }
+    }
+
+    fn write_place(&mut self, place: Place, value: Object) -> RloxResult<Object> {
+        match place {
+            Place::Variable(var) => self.assign_variable(var, value),
+            Place::Property(instance, name) => Ok(Self::set_property(&instance, name, value)),
+            Place::Index(collection, bracket, index) => {
+                Self::set_index(bracket, &collection, index, value.clone())?;
+                Ok(value)
+            }
+        }
+    }
+
+    fn evaluate_instance(
+        &mut self,
+        object: &Expr,
+        name: &Token,
+        msg: &str,
+    ) -> RloxResult<Rc<Instance>> {
+        match self.evaluate(object)? {
+            Object::Instance(instance) => Ok(instance),
+            _ => Err(Error::Runtime(name.line, String::from(msg))),
+        }
+    }
+
+    fn get_property(instance: &Rc<Instance>, name: &Token) -> RloxResult<Object> {
+        if let Some(value) = instance.fields.borrow().get(&name.lexeme) {
+            return Ok(value.clone());
+        }
+
+        match instance.class.find_method(&name.lexeme) {
+            Some(method) => Ok(Object::Function(Rc::new(method.bind(Rc::clone(instance))))),
+            None => Err(Error::Runtime(
+                name.line,
+                format!("Undefined property '{}'.", name.lexeme),
+            )),
+        }
+    }
+
+    fn set_property(instance: &Instance, name: &Token, value: Object) -> Object {
+        instance
+            .fields
+            .borrow_mut()
+            .insert(name.lexeme.to_owned(), value.clone());
+
+        value
+    }
+
+    /// Reads the element of a list or the value of a map key, missing keys read as nil.
+    fn get_index(bracket: &Token, collection: &Object, index: &Object) -> RloxResult<Object> {
+        match collection {
+            Object::List(list) => {
+                let list = list.borrow();
+                let position = Self::list_index(bracket, index, list.len())?;
+
+                Ok(list[position].clone())
+            }
+            Object::Map(map) => map
+                .borrow()
+                .get(index)
+                .map(|value| value.cloned().unwrap_or(Object::Nil))
+                .map_err(|msg| Error::Runtime(bracket.line, msg)),
+            _ => Err(Error::Runtime(
+                bracket.line,
+                String::from("Only lists and maps can be indexed."),
+            )),
+        }
+    }
+
+    fn set_index(
+        bracket: &Token,
+        collection: &Object,
+        index: Object,
+        value: Object,
+    ) -> RloxResult<()> {
+        match collection {
+            Object::List(list) => {
+                let mut list = list.borrow_mut();
+                let position = Self::list_index(bracket, &index, list.len())?;
+                list[position] = value;
+
+                Ok(())
+            }
+            Object::Map(map) => map
+                .borrow_mut()
+                .insert(index, value)
+                .map_err(|msg| Error::Runtime(bracket.line, msg)),
+            _ => Err(Error::Runtime(
+                bracket.line,
+                String::from("Only lists and maps can be indexed."),
+            )),
+        }
+    }
+
+    /// Converts the index value into a position in the list, checking the list bounds.
+    fn list_index(bracket: &Token, index: &Object, len: usize) -> RloxResult<usize> {
+        let position = match index {
+            Object::Integer(value) => usize::try_from(*value).ok().filter(|value| *value < len),
+            Object::BigInt(_) => None,
+            _ => {
+                return Err(Error::Runtime(
+                    bracket.line,
+                    String::from("List index must be an integer."),
+                ))
+            }
+        };
+
+        position.ok_or_else(|| {
+            Error::Runtime(
+                bracket.line,
+                format!("List index {} out of bounds for length {}.", index, len),
+            )
+        })
+    }
+
+    fn check_arity(paren: &Token, expected: usize, got: usize) -> RloxResult<()> {
+        if expected != got {
+            return Err(Error::Runtime(
+                paren.line,
+                format!("Expected {} arguments but got {}.", expected, got),
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn matches_pattern(pattern: &Pattern, value: &Object) -> bool {
+        match pattern {
+            Pattern::Wildcard | Pattern::Binding(_) => true,
+            Pattern::Literal(literal) => Object::from(literal) == *value,
+            Pattern::Range(start, end, inclusive) => {
+                let (start, end) = (Object::from(start), Object::from(end));
+                match (value.partial_cmp(&start), value.partial_cmp(&end)) {
+                    (Some(cmp::Ordering::Less), _) | (None, _) | (_, None) => false,
+                    (_, Some(cmp::Ordering::Less)) => true,
+                    (_, Some(cmp::Ordering::Equal)) => *inclusive,
+                    (_, Some(cmp::Ordering::Greater)) => false,
+                }
+            }
+        }
+    }
+
+    /// Executes the arm body if its guard passes, returns `None` otherwise.
+    fn execute_arm(&mut self, arm: &MatchArm) -> RloxResult<Option<Completion>> {
+        if let Some(guard) = &arm.guard {
+            if !self.evaluate(guard)?.is_truthy() {
+                return Ok(None);
+            }
+        }
+
+        self.execute(&arm.body).map(Some)
+    }
+
+    fn iteration(name: &Token, iterable: Object) -> RloxResult<Iteration> {
+        match iterable {
+            Object::String(value) => Ok(Iteration::Values(
+                value
+                    .chars()
+                    .map(|c| Object::String(c.to_string()))
+                    .collect::<Vec<_>>()
+                    .into_iter(),
+            )),
+            Object::Range(range) => Ok(Iteration::Range(
+                range.start as i128,
+                range.end as i128 + range.inclusive as i128,
+            )),
+            Object::List(list) => Ok(Iteration::List(list, 0)),
+            Object::Map(map) => Ok(Iteration::Values(
+                map.borrow()
+                    .iter()
+                    .map(|(key, _)| key.clone())
+                    .collect::<Vec<_>>()
+                    .into_iter(),
+            )),
+            Object::Instance(instance) => match instance.class.find_method("next") {
+                Some(method) if method.arity() == 0 => {
+                    Ok(Iteration::Iterator(method.bind(Rc::clone(&instance))))
+                }
+                Some(_) => Err(Error::Runtime(
+                    name.line,
+                    String::from("Iterator 'next' method can't take parameters."),
+                )),
+                None => Err(Error::Runtime(
+                    name.line,
+                    String::from("Only instances with a 'next' method can be iterated."),
+                )),
             },
-            BinaryOp::Minus => match (left, right) {
-                (Object::Number(a), Object::Number(b)) => Ok(Object::Number(a - b)),
-                (_, _) => Err(self.err("Operands must be numbers.")),
+            _ => Err(Error::Runtime(
+                name.line,
+                String::from("Can only iterate over strings, ranges, lists, maps and iterators."),
+            )),
+        }
+    }
+
+    fn next_value(
+        &mut self,
+        name: &Token,
+        iteration: &mut Iteration,
+    ) -> RloxResult<Option<Object>> {
+        match iteration {
+            Iteration::Values(values) => Ok(values.next()),
+            Iteration::Range(next, end) => {
+                if *next >= *end {
+                    return Ok(None);
+                }
+
+                *next += 1;
+                Ok(Some(Object::Integer((*next - 1) as i64)))
+            }
+            Iteration::List(list, index) => {
+                let value = list.borrow().get(*index).cloned();
+                *index += 1;
+                Ok(value)
+            }
+            Iteration::Iterator(next) => match self.call_function(name, next, vec![])? {
+                Object::Nil => Ok(None),
+                value => Ok(Some(value)),
             },
-            BinaryOp::Star => match (left, right) {
-                (Object::Number(a), Object::Number(b)) => Ok(Object::Number(a * b)),
-                (_, _) => Err(self.err("Operands must be numbers.")),
+        }
+    }
+
+    /// Defines the names of the pattern in the current environment.
+    fn bind_pattern(
+        &self,
+        pattern: &BindingPattern,
+        equals: &Token,
+        value: Object,
+    ) -> RloxResult<()> {
+        match pattern {
+            BindingPattern::Name(name) => {
+                self.environment.define(&name.lexeme, value);
+                Ok(())
+            }
+            BindingPattern::Tuple(patterns) => {
+                let values = Self::unpack(equals, value, patterns.len())?;
+                for (pattern, value) in patterns.iter().zip(values.iter()) {
+                    self.bind_pattern(pattern, equals, value.clone())?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Assigns the value to the target of a destructuring assignment.
+    fn assign_target(&mut self, target: &Expr, equals: &Token, value: Object) -> RloxResult<()> {
+        match target {
+            Expr::Tuple(targets) => {
+                let values = Self::unpack(equals, value, targets.len())?;
+                for (target, value) in targets.iter().zip(values.iter()) {
+                    self.assign_target(target, equals, value.clone())?;
+                }
+                Ok(())
+            }
+            _ => {
+                let place = self.evaluate_place(target)?;
+                self.write_place(place, value)?;
+                Ok(())
+            }
+        }
+    }
+
+    fn unpack(equals: &Token, value: Object, arity: usize) -> RloxResult<Rc<Vec<Object>>> {
+        match value {
+            Object::Tuple(values) if values.len() == arity => Ok(values),
+            Object::Tuple(values) => Err(Error::Runtime(
+                equals.line,
+                format!("Expected {} values to unpack, got {}.", arity, values.len()),
+            )),
+            _ => Err(Error::Runtime(
+                equals.line,
+                String::from("Only tuples can be destructured."),
+            )),
+        }
+    }
+
+    /// Applies an arithmetic operator. Integers overflowing an i64 are promoted to big integers,
+    /// big integers and rationals are computed exactly unless mixed with a float.
+    fn arithmetic(
+        left: &Object,
+        right: &Object,
+        integer_op: fn(i64, i64) -> Option<i64>,
+        exact_op: fn(&Rational, &Rational) -> Rational,
+        float_op: fn(f64, f64) -> f64,
+    ) -> Result<Object, String> {
+        if let (Object::Integer(a), Object::Integer(b)) = (left, right) {
+            if let Some(value) = integer_op(*a, *b) {
+                return Ok(Object::Integer(value));
+            }
+        }
+
+        if let Some((a, b)) = Self::exact_operands(left, right) {
+            return Ok(Object::from_exact(exact_op(&a, &b)));
+        }
+
+        match (left.to_float(), right.to_float()) {
+            (Some(a), Some(b)) => Ok(Object::Number(float_op(a, b))),
+            _ => Err(String::from("Operands must be numbers.")),
+        }
+    }
+
+    /// Divides exactly unless a float is involved. The quotient of integers is an integer when
+    /// the division is exact, a rational otherwise.
+    fn division(left: &Object, right: &Object) -> Result<Object, String> {
+        match Self::exact_operands(left, right) {
+            Some((a, b)) => a
+                .checked_div(&b)
+                .map(Object::from_exact)
+                .ok_or_else(|| String::from("Division by zero.")),
+            _ => match (left.to_float(), right.to_float()) {
+                (Some(a), Some(b)) => Ok(Object::Number(a / b)),
+                _ => Err(String::from("Operands must be numbers.")),
             },
-            BinaryOp::Slash => match (left, right) {
-                (Object::Number(a), Object::Number(b)) => Ok(Object::Number(a / b)),
-                (_, _) => Err(self.err("Operands must be numbers.")),
+        }
+    }
+
+    /// Divides and truncates the result to an integer.
+    fn integer_division(left: &Object, right: &Object) -> Result<Object, String> {
+        if let (Object::Integer(a), Object::Integer(b)) = (left, right) {
+            if *b == 0 {
+                return Err(String::from("Division by zero."));
+            }
+
+            if let Some(value) = a.checked_div(*b) {
+                return Ok(Object::Integer(value));
+            }
+        }
+
+        if let Some((a, b)) = Self::exact_operands(left, right) {
+            return a
+                .checked_div(&b)
+                .map(|value| Object::from_exact(Rational::from_integer(value.trunc())))
+                .ok_or_else(|| String::from("Division by zero."));
+        }
+
+        match (left.to_float(), right.to_float()) {
+            (Some(_), Some(0.0)) => Err(String::from("Division by zero.")),
+            (Some(a), Some(b)) => Rational::from_f64((a / b).trunc())
+                .map(Object::from_exact)
+                .ok_or_else(|| String::from("Integer division result is out of range.")),
+            _ => Err(String::from("Operands must be numbers.")),
+        }
+    }
+
+    /// Remainder of the division truncated toward zero, it has the sign of the dividend.
+    fn remainder(left: &Object, right: &Object) -> Result<Object, String> {
+        if let (Object::Integer(a), Object::Integer(b)) = (left, right) {
+            if *b == 0 {
+                return Err(String::from("Division by zero."));
+            }
+
+            if let Some(value) = a.checked_rem(*b) {
+                return Ok(Object::Integer(value));
+            }
+        }
+
+        if let Some((a, b)) = Self::exact_operands(left, right) {
+            let quotient = a
+                .checked_div(&b)
+                .ok_or_else(|| String::from("Division by zero."))?;
+            let quotient = Rational::from_integer(quotient.trunc());
+
+            return Ok(Object::from_exact(&a - &(&b * &quotient)));
+        }
+
+        match (left.to_float(), right.to_float()) {
+            (Some(a), Some(b)) => Ok(Object::Number(a % b)),
+            _ => Err(String::from("Operands must be numbers.")),
+        }
+    }
+
+    /// Raises to an integer power exactly, a negative power of an integer is a rational. Other
+    /// powers are computed with floats.
+    fn power(left: &Object, right: &Object) -> Result<Object, String> {
+        let exponent = match right {
+            Object::Integer(exponent) if Self::exact_operands(left, right).is_some() => *exponent,
+            Object::BigInt(_) if Self::exact_operands(left, right).is_some() => {
+                return Err(String::from("Exponent is too large."))
+            }
+            _ => {
+                return match (left.to_float(), right.to_float()) {
+                    (Some(a), Some(b)) => Ok(Object::Number(a.powf(b))),
+                    _ => Err(String::from("Operands must be numbers.")),
+                }
+            }
+        };
+
+        let magnitude = u32::try_from(exponent.unsigned_abs())
+            .map_err(|_| String::from("Exponent is too large."))?;
+        if let (Object::Integer(base), false) = (left, exponent < 0) {
+            if let Some(value) = base.checked_pow(magnitude) {
+                return Ok(Object::Integer(value));
+            }
+        }
+
+        let base = left.to_exact().expect("exact operand");
+        // The magnitude of the base is at least 2 to the power of its bits minus one, and so the
+        // result is at least 2 to the power of that times the exponent.
+        let bits = cmp::max(base.numerator().bits(), base.denominator().bits());
+        if bits.saturating_sub(1) * magnitude as u64 > MAX_INTEGER_BITS {
+            return Err(String::from("Exponent is too large."));
+        }
+
+        let value = base.pow(magnitude);
+        if exponent < 0 {
+            return Rational::from_integer(BigInt::one())
+                .checked_div(&value)
+                .map(Object::from_exact)
+                .ok_or_else(|| String::from("Division by zero."));
+        }
+
+        Ok(Object::from_exact(value))
+    }
+
+    /// Applies a bitwise operator, on integers fitting in 64 bits.
+    fn bitwise(left: &Object, right: &Object, op: fn(i64, i64) -> i64) -> Result<Object, String> {
+        match (left, right) {
+            (Object::Integer(a), Object::Integer(b)) => Ok(Object::Integer(op(*a, *b))),
+            _ => Err(Self::integer_operands_err(left, right)),
+        }
+    }
+
+    /// Multiplies by a power of two, promoting to a big integer on overflow.
+    fn shift_left(left: &Object, right: &Object) -> Result<Object, String> {
+        let bits = Self::shift_amount(left, right)?;
+        if let Object::Integer(value) = left {
+            if bits < 64 && (value << bits) >> bits == *value {
+                return Ok(Object::Integer(value << bits));
+            }
+        }
+
+        let value = left.to_exact().expect("integer operand");
+        if !value.is_zero() && value.numerator().bits().saturating_add(bits) > MAX_INTEGER_BITS {
+            return Err(String::from("Shift amount is too large."));
+        }
+
+        let value = value.numerator().shl(bits as u32);
+
+        Ok(Object::from_exact(Rational::from_integer(value)))
+    }
+
+    /// Divides by a power of two, rounding toward negative infinity like an arithmetic shift.
+    fn shift_right(left: &Object, right: &Object) -> Result<Object, String> {
+        let bits = Self::shift_amount(left, right)?;
+        if let Object::Integer(value) = left {
+            return Ok(Object::Integer(value >> bits.min(63)));
+        }
+
+        let value = left.to_exact().expect("integer operand");
+        let divisor = match u32::try_from(bits) {
+            Ok(bits) => BigInt::one().shl(bits),
+            // Larger than any big integer.
+            Err(_) => {
+                return Ok(Object::Integer(if value.numerator().is_negative() {
+                    -1
+                } else {
+                    0
+                }))
+            }
+        };
+
+        let (quotient, remainder) = value.numerator().div_rem(&divisor);
+        let quotient = match remainder.is_negative() {
+            true => &quotient - &BigInt::one(),
+            false => quotient,
+        };
+
+        Ok(Object::from_exact(Rational::from_integer(quotient)))
+    }
+
+    fn shift_amount(left: &Object, right: &Object) -> Result<u64, String> {
+        match (left, right) {
+            (Object::Integer(_) | Object::BigInt(_), Object::Integer(bits)) => {
+                u64::try_from(*bits).map_err(|_| String::from("Shift amount can't be negative."))
+            }
+            (Object::Integer(_) | Object::BigInt(_), Object::BigInt(_)) => {
+                Err(String::from("Shift amount is too large."))
+            }
+            _ => Err(String::from("Operands must be integers.")),
+        }
+    }
+
+    fn integer_operands_err(left: &Object, right: &Object) -> String {
+        match (left, right) {
+            (Object::Integer(_) | Object::BigInt(_), Object::Integer(_) | Object::BigInt(_)) => {
+                String::from("Bitwise operands must fit in 64 bits.")
+            }
+            _ => String::from("Operands must be integers."),
+        }
+    }
+
+    /// Exact values of the operands, `None` unless both are integers or rationals.
+    fn exact_operands(left: &Object, right: &Object) -> Option<(Rational, Rational)> {
+        match (left, right) {
+            (Object::Number(_), _) | (_, Object::Number(_)) => None,
+            _ => Some((left.to_exact()?, right.to_exact()?)),
+        }
+    }
+
+    /// Applies a binary operator to evaluated operands.
+    fn binary_op(left: Object, op: &BinaryOp, right: Object) -> Result<Object, String> {
+        match op {
+            BinaryOp::Plus => match (&left, &right) {
+                (Object::String(a), Object::String(b)) => Ok(Object::String(format!("{}{}", a, b))),
+                (a, b) if a.is_number() && b.is_number() => {
+                    Self::arithmetic(&left, &right, i64::checked_add, |a, b| a + b, |a, b| a + b)
+                }
+                (_, _) => Err(String::from("Operands must be numbers or strings.")),
             },
+            BinaryOp::Minus => {
+                Self::arithmetic(&left, &right, i64::checked_sub, |a, b| a - b, |a, b| a - b)
+            }
+            BinaryOp::Star => {
+                Self::arithmetic(&left, &right, i64::checked_mul, |a, b| a * b, |a, b| a * b)
+            }
+            BinaryOp::Slash => Self::division(&left, &right),
+            BinaryOp::TildeSlash => Self::integer_division(&left, &right),
+            BinaryOp::Percent => Self::remainder(&left, &right),
+            BinaryOp::StarStar => Self::power(&left, &right),
+            BinaryOp::Ampersand => Self::bitwise(&left, &right, |a, b| a & b),
+            BinaryOp::Pipe => Self::bitwise(&left, &right, |a, b| a | b),
+            BinaryOp::Caret => Self::bitwise(&left, &right, |a, b| a ^ b),
+            BinaryOp::LessLess => Self::shift_left(&left, &right),
+            BinaryOp::GreaterGreater => Self::shift_right(&left, &right),
             BinaryOp::Equal => match left.partial_cmp(&right) {
                 Some(cmp::Ordering::Equal) => Ok(Object::True),
                 _ => Ok(Object::False),
@@ -95,49 +868,115 @@ impl<W: io::Write> ExprVisitor<RloxResult<Object>> for Interpreter<W> {
             BinaryOp::Greater => match left.partial_cmp(&right) {
                 Some(cmp::Ordering::Greater) => Ok(Object::True),
                 Some(_) => Ok(Object::False),
-                _ => Err(self.err(&format!(
+                _ => Err(format!(
                     "Invalid comparison between types (left: {:?}, right: {:?}).",
                     left, right
-                ))),
+                )),
             },
             BinaryOp::GreaterEqual => match left.partial_cmp(&right) {
                 Some(cmp::Ordering::Greater) | Some(cmp::Ordering::Equal) => Ok(Object::True),
                 Some(_) => Ok(Object::False),
-                _ => Err(self.err(&format!(
+                _ => Err(format!(
                     "Invalid comparison between types (left: {:?}, right: {:?}).",
                     left, right
-                ))),
+                )),
             },
             BinaryOp::Less => match left.partial_cmp(&right) {
                 Some(cmp::Ordering::Less) => Ok(Object::True),
                 Some(_) => Ok(Object::False),
-                _ => Err(self.err(&format!(
+                _ => Err(format!(
                     "Invalid comparison between types (left: {:?}, right: {:?}).",
                     left, right
-                ))),
+                )),
             },
             BinaryOp::LessEqual => match left.partial_cmp(&right) {
                 Some(cmp::Ordering::Less) | Some(cmp::Ordering::Equal) => Ok(Object::True),
                 Some(_) => Ok(Object::False),
-                _ => Err(self.err(&format!(
+                _ => Err(format!(
                     "Invalid comparison between types (left: {:?}, right: {:?}).",
                     left, right
-                ))),
+                )),
             },
         }
     }
+}
 
-    fn visit_unary_expr(&mut self, op: &UnaryOp, right: &Expr) -> RloxResult<Object> {
+impl<W: io::Write> ExprVisitor<RloxResult<Object>> for Interpreter<W> {
+    fn visit_binary_expr(
+        &mut self,
+        left: &Expr,
+        operator: &Token,
+        op: &BinaryOp,
+        right: &Expr,
+    ) -> RloxResult<Object> {
+        let left = self.evaluate(left)?;
         let right = self.evaluate(right)?;
 
+        Self::binary_op(left, op, right).map_err(|msg| Error::Runtime(operator.line, msg))
+    }
+
+    fn visit_logical_expr(
+        &mut self,
+        left: &Expr,
+        op: &LogicalOp,
+        right: &Expr,
+    ) -> RloxResult<Object> {
+        let left = self.evaluate(left)?;
+
         match op {
-            UnaryOp::Neg => Ok(Object::from(!right.is_truthy())),
-            UnaryOp::Not => match right {
-                Object::Number(value) => Ok(Object::Number(value * -1.0)),
-                _ => Err(self.err("Operand must be a number.")),
-            },
+            LogicalOp::Or if left.is_truthy() => Ok(left),
+            LogicalOp::And if !left.is_truthy() => Ok(left),
+            LogicalOp::NilCoalesce if left != Object::Nil => Ok(left),
+            _ => self.evaluate(right),
+        }
+    }
+
+    fn visit_conditional_expr(
+        &mut self,
+        condition: &Expr,
+        then_branch: &Expr,
+        else_branch: &Expr,
+    ) -> RloxResult<Object> {
+        if self.evaluate(condition)?.is_truthy() {
+            self.evaluate(then_branch)
+        } else {
+            self.evaluate(else_branch)
         }
     }
+
+    fn visit_comma_expr(&mut self, left: &Expr, right: &Expr) -> RloxResult<Object> {
+        self.evaluate(left)?;
+        self.evaluate(right)
+    }
+
+    fn visit_unary_expr(
+        &mut self,
+        operator: &Token,
+        op: &UnaryOp,
+        right: &Expr,
+    ) -> RloxResult<Object> {
+        let right = self.evaluate(right)?;
+
+        let result = match op {
+            UnaryOp::Not => Ok(Object::from(!right.is_truthy())),
+            UnaryOp::Neg => match right {
+                Object::Integer(value) if value != i64::MIN => Ok(Object::Integer(-value)),
+                Object::Number(value) => Ok(Object::Number(-value)),
+                _ => match right.to_exact() {
+                    Some(value) => Ok(Object::from_exact(-&value)),
+                    None => Err("Operand must be a number."),
+                },
+            },
+            UnaryOp::BitNot => match right {
+                Object::Integer(value) => Ok(Object::Integer(!value)),
+                Object::BigInt(_) => Err("Bitwise operands must fit in 64 bits."),
+                _ => Err("Operand must be an integer."),
+            },
+        };
+
+        result.map_err(|msg| Error::Runtime(operator.line, String::from(msg)))
+    }
+
     fn visit_grouping_expr(&mut self, expr: &Expr) -> RloxResult<Object> {
         self.evaluate(expr)
     }
@@ -146,39 +985,514 @@ impl<W: io::Write> ExprVisitor<RloxResult<Object>> for Interpreter<W> {
         Ok(Object::from(value))
     }
 
-    fn visit_variable_expr(&mut self, name: &Token) -> RloxResult<Object> {
-        self.environment.get(name)
+    fn visit_interpolation_expr(&mut self, parts: &[Expr]) -> RloxResult<Object> {
+        let mut value = String::new();
+        for part in parts {
+            value.push_str(&self.evaluate(part)?.to_string());
+        }
+
+        Ok(Object::String(value))
+    }
+
+    fn visit_variable_expr(&mut self, var: &VarRef) -> RloxResult<Object> {
+        match var.depth.get() {
+            Some(depth) => Ok(self.environment.get_at(depth, &var.id)),
+            None => self.globals.get(&var.id),
+        }
     }
 
-    fn visit_assignment_expr(&mut self, id: &Token, expr: &Expr) -> RloxResult<Object> {
+    fn visit_assignment_expr(&mut self, var: &VarRef, expr: &Expr) -> RloxResult<Object> {
         let value = self.evaluate(expr)?;
-        self.environment.assign(id, value)
+        self.assign_variable(var, value)
+    }
+
+    fn visit_call_expr(
+        &mut self,
+        callee: &Expr,
+        paren: &Token,
+        args: &[Expr],
+    ) -> RloxResult<Object> {
+        let callee = self.evaluate(callee)?;
+
+        let mut values = Vec::with_capacity(args.len());
+        for arg in args {
+            values.push(self.evaluate(arg)?);
+        }
+
+        match callee {
+            Object::Function(function) => {
+                Self::check_arity(paren, function.arity(), values.len())?;
+                self.call_function(paren, &function, values)
+            }
+            Object::Class(class) => {
+                Self::check_arity(paren, class.arity(), values.len())?;
+                self.call_class(paren, class, values)
+            }
+            Object::Native(native) => {
+                Self::check_arity(paren, native.arity, values.len())?;
+                (native.function)(&values).map_err(|msg| Error::Runtime(paren.line, msg))
+            }
+            _ => Err(Error::Runtime(
+                paren.line,
+                String::from("Can only call functions and classes."),
+            )),
+        }
+    }
+
+    fn visit_get_expr(&mut self, object: &Expr, name: &Token) -> RloxResult<Object> {
+        match self.evaluate(object)? {
+            Object::Instance(instance) => Self::get_property(&instance, name),
+            Object::Module(module) => module.globals.get_local(&name.lexeme).ok_or_else(|| {
+                Error::Runtime(
+                    name.line,
+                    format!("Undefined binding '{}' in module.", name.lexeme),
+                )
+            }),
+            _ => Err(Error::Runtime(
+                name.line,
+                String::from("Only instances have properties."),
+            )),
+        }
+    }
+
+    fn visit_set_expr(&mut self, object: &Expr, name: &Token, value: &Expr) -> RloxResult<Object> {
+        let instance = self.evaluate_instance(object, name, "Only instances have fields.")?;
+
+        let value = self.evaluate(value)?;
+        Ok(Self::set_property(&instance, name, value))
+    }
+
+    fn visit_nil_coalesce_assign_expr(
+        &mut self,
+        target: &Expr,
+        value: &Expr,
+    ) -> RloxResult<Object> {
+        let place = self.evaluate_place(target)?;
+        let current = self.read_place(&place)?;
+        if current != Object::Nil {
+            return Ok(current);
+        }
+
+        let value = self.evaluate(value)?;
+        self.write_place(place, value)
+    }
+
+    fn visit_compound_assign_expr(
+        &mut self,
+        target: &Expr,
+        operator: &Token,
+        op: &BinaryOp,
+        value: &Expr,
+        postfix: bool,
+    ) -> RloxResult<Object> {
+        let place = self.evaluate_place(target)?;
+        let current = self.read_place(&place)?;
+        let value = self.evaluate(value)?;
+
+        let updated = Self::binary_op(current.clone(), op, value)
+            .map_err(|msg| Error::Runtime(operator.line, msg))?;
+        self.write_place(place, updated.clone())?;
+
+        Ok(if postfix { current } else { updated })
+    }
+
+    fn visit_destructure_assign_expr(
+        &mut self,
+        target: &Expr,
+        equals: &Token,
+        value: &Expr,
+    ) -> RloxResult<Object> {
+        let value = self.evaluate(value)?;
+
+        self.assign_target(target, equals, value.clone())?;
+        Ok(value)
+    }
+
+    fn visit_tuple_expr(&mut self, elements: &[Expr]) -> RloxResult<Object> {
+        let mut values = Vec::with_capacity(elements.len());
+        for element in elements {
+            values.push(self.evaluate(element)?);
+        }
+
+        Ok(Object::Tuple(Rc::new(values)))
+    }
+
+    fn visit_list_expr(&mut self, elements: &[Expr]) -> RloxResult<Object> {
+        let mut values = Vec::with_capacity(elements.len());
+        for element in elements {
+            values.push(self.evaluate(element)?);
+        }
+
+        Ok(Object::List(Rc::new(RefCell::new(values))))
+    }
+
+    fn visit_range_expr(
+        &mut self,
+        start: &Expr,
+        operator: &Token,
+        end: &Expr,
+        inclusive: bool,
+    ) -> RloxResult<Object> {
+        let start = self.evaluate(start)?;
+        let end = self.evaluate(end)?;
+
+        match (start, end) {
+            (Object::Integer(start), Object::Integer(end)) => Ok(Object::Range(Range {
+                start,
+                end,
+                inclusive,
+            })),
+            _ => Err(Error::Runtime(
+                operator.line,
+                String::from("Range bounds must be integers."),
+            )),
+        }
+    }
+
+    fn visit_map_expr(&mut self, brace: &Token, entries: &[(Expr, Expr)]) -> RloxResult<Object> {
+        let mut map = Map::new();
+        for (key, value) in entries {
+            let key = self.evaluate(key)?;
+            let value = self.evaluate(value)?;
+            map.insert(key, value)
+                .map_err(|msg| Error::Runtime(brace.line, msg))?;
+        }
+
+        Ok(Object::Map(Rc::new(RefCell::new(map))))
+    }
+
+    fn visit_index_expr(
+        &mut self,
+        object: &Expr,
+        bracket: &Token,
+        index: &Expr,
+    ) -> RloxResult<Object> {
+        let collection = self.evaluate(object)?;
+        let index = self.evaluate(index)?;
+
+        Self::get_index(bracket, &collection, &index)
+    }
+
+    fn visit_index_set_expr(
+        &mut self,
+        object: &Expr,
+        bracket: &Token,
+        index: &Expr,
+        value: &Expr,
+    ) -> RloxResult<Object> {
+        let collection = self.evaluate(object)?;
+        let index = self.evaluate(index)?;
+        let value = self.evaluate(value)?;
+
+        Self::set_index(bracket, &collection, index, value.clone())?;
+
+        Ok(value)
+    }
+
+    fn visit_this_expr(&mut self, keyword: &VarRef) -> RloxResult<Object> {
+        self.visit_variable_expr(keyword)
+    }
+
+    fn visit_super_expr(&mut self, keyword: &VarRef, method: &Token) -> RloxResult<Object> {
+        let depth = keyword
+            .depth
+            .get()
+            .expect("'super' should always be resolved.");
+
+        let superclass = match self.environment.get_at(depth, &keyword.id) {
+            Object::Class(class) => class,
+            _ => unreachable!("'super' is always bound to a class."),
+        };
+        // `this` is always bound in the environment right inside the one holding `super`.
+        let instance = match self.environment.get_internal_at(depth - 1, "this") {
+            Object::Instance(instance) => instance,
+            _ => unreachable!("'this' is always bound to an instance."),
+        };
+
+        match superclass.find_method(&method.lexeme) {
+            Some(method) => Ok(Object::Function(Rc::new(method.bind(instance)))),
+            None => Err(Error::Runtime(
+                method.line,
+                format!("Undefined property '{}'.", method.lexeme),
+            )),
+        }
     }
 }
 
-impl<W: io::Write> StmtVisitor<RloxResult<()>> for Interpreter<W> {
-    fn visit_expression_stmt(&mut self, expr: &Expr) -> RloxResult<()> {
+impl<W: io::Write> StmtVisitor<RloxResult<Completion>> for Interpreter<W> {
+    fn visit_expression_stmt(&mut self, expr: &Expr) -> RloxResult<Completion> {
         self.evaluate(expr)?;
-        Ok(())
+        Ok(Completion::Normal)
     }
 
-    fn visit_var_stmt(&mut self, id: &Token, intializer: &Option<Box<Expr>>) -> RloxResult<()> {
+    fn visit_var_stmt(
+        &mut self,
+        id: &Token,
+        intializer: &Option<Box<Expr>>,
+    ) -> RloxResult<Completion> {
         let value = match intializer {
             Some(expr) => self.evaluate(expr)?,
             None => Object::Nil,
         };
 
-        self.environment.define(id, value);
-        Ok(())
+        self.environment.define(&id.lexeme, value);
+        Ok(Completion::Normal)
+    }
+
+    fn visit_var_destructure_stmt(
+        &mut self,
+        pattern: &BindingPattern,
+        equals: &Token,
+        initializer: &Expr,
+    ) -> RloxResult<Completion> {
+        let value = self.evaluate(initializer)?;
+
+        self.bind_pattern(pattern, equals, value)?;
+        Ok(Completion::Normal)
     }
 
-    fn visit_print_stmt(&mut self, expr: &Expr) -> RloxResult<()> {
+    fn visit_const_stmt(&mut self, id: &Token, initializer: &Expr) -> RloxResult<Completion> {
+        let value = self.evaluate(initializer)?;
+
+        self.environment.define_const(&id.lexeme, value);
+        Ok(Completion::Normal)
+    }
+
+    fn visit_print_stmt(&mut self, expr: &Expr) -> RloxResult<Completion> {
         let value = self.evaluate(expr)?;
         writeln!(self.out, "{}", value).unwrap();
-        Ok(())
+        Ok(Completion::Normal)
     }
 
-    fn visit_block_stmt(&mut self, body: &[Stmt]) -> RloxResult<()> {
+    fn visit_block_stmt(&mut self, body: &[Stmt]) -> RloxResult<Completion> {
         self.execute_block(body, Environment::from(&self.environment))
     }
+
+    fn visit_if_stmt(
+        &mut self,
+        condition: &Expr,
+        then_branch: &Stmt,
+        else_branch: &Option<Box<Stmt>>,
+    ) -> RloxResult<Completion> {
+        if self.evaluate(condition)?.is_truthy() {
+            self.execute(then_branch)
+        } else if let Some(else_branch) = else_branch {
+            self.execute(else_branch)
+        } else {
+            Ok(Completion::Normal)
+        }
+    }
+
+    fn visit_while_stmt(
+        &mut self,
+        condition: &Expr,
+        body: &Stmt,
+        increment: &Option<Box<Expr>>,
+    ) -> RloxResult<Completion> {
+        while self.evaluate(condition)?.is_truthy() {
+            match self.execute(body)? {
+                Completion::Break => break,
+                Completion::Return(value) => return Ok(Completion::Return(value)),
+                Completion::Normal | Completion::Continue => {}
+            }
+
+            if let Some(increment) = increment {
+                self.evaluate(increment)?;
+            }
+        }
+
+        Ok(Completion::Normal)
+    }
+
+    fn visit_for_in_stmt(
+        &mut self,
+        name: &Token,
+        iterable: &Expr,
+        body: &Stmt,
+    ) -> RloxResult<Completion> {
+        let iterable = self.evaluate(iterable)?;
+        let mut iteration = Self::iteration(name, iterable)?;
+
+        // Every iteration gets a fresh binding, closures created in the body keep their value.
+        while let Some(value) = self.next_value(name, &mut iteration)? {
+            let iteration_env = Environment::from(&self.environment);
+            iteration_env.define(&name.lexeme, value);
+
+            let previous_env = mem::replace(&mut self.environment, iteration_env);
+            let completion = self.execute(body);
+            self.environment = previous_env;
+
+            match completion? {
+                Completion::Break => break,
+                Completion::Return(value) => return Ok(Completion::Return(value)),
+                Completion::Normal | Completion::Continue => {}
+            }
+        }
+
+        Ok(Completion::Normal)
+    }
+
+    fn visit_break_stmt(&mut self) -> RloxResult<Completion> {
+        Ok(Completion::Break)
+    }
+
+    fn visit_continue_stmt(&mut self) -> RloxResult<Completion> {
+        Ok(Completion::Continue)
+    }
+
+    fn visit_function_stmt(&mut self, declaration: &Rc<FunctionDecl>) -> RloxResult<Completion> {
+        let function = Function {
+            declaration: Rc::clone(declaration),
+            closure: Rc::clone(&self.environment),
+            globals: Rc::clone(&self.globals),
+            is_initializer: false,
+        };
+
+        self.environment.define(
+            &declaration.name.lexeme,
+            Object::Function(Rc::new(function)),
+        );
+        Ok(Completion::Normal)
+    }
+
+    fn visit_return_stmt(
+        &mut self,
+        _keyword: &Token,
+        value: &Option<Box<Expr>>,
+    ) -> RloxResult<Completion> {
+        let value = match value {
+            Some(expr) => self.evaluate(expr)?,
+            None => Object::Nil,
+        };
+
+        Ok(Completion::Return(value))
+    }
+
+    fn visit_match_stmt(
+        &mut self,
+        keyword: &Token,
+        subject: &Expr,
+        arms: &[MatchArm],
+    ) -> RloxResult<Completion> {
+        let value = self.evaluate(subject)?;
+
+        for arm in arms {
+            if !Self::matches_pattern(&arm.pattern, &value) {
+                continue;
+            }
+
+            let arm_env = Environment::from(&self.environment);
+            if let Pattern::Binding(id) = &arm.pattern {
+                arm_env.define(&id.lexeme, value.clone());
+            }
+
+            let previous_env = mem::replace(&mut self.environment, arm_env);
+            let completion = self.execute_arm(arm);
+            self.environment = previous_env;
+
+            if let Some(completion) = completion? {
+                return Ok(completion);
+            }
+        }
+
+        Err(Error::Runtime(
+            keyword.line,
+            format!("No match arm for value {}.", value),
+        ))
+    }
+
+    fn visit_class_stmt(&mut self, declaration: &ClassDecl) -> RloxResult<Completion> {
+        let superclass = match &declaration.superclass {
+            Some(superclass) => match self.visit_variable_expr(superclass)? {
+                Object::Class(class) => Some(class),
+                _ => {
+                    return Err(Error::Runtime(
+                        superclass.id.line,
+                        String::from("Superclass must be a class."),
+                    ))
+                }
+            },
+            None => None,
+        };
+
+        // Methods of a subclass close over an extra environment binding `super`.
+        let closure = match &superclass {
+            Some(superclass) => {
+                let env = Environment::from(&self.environment);
+                env.define("super", Object::Class(Rc::clone(superclass)));
+                env
+            }
+            None => Rc::clone(&self.environment),
+        };
+
+        let methods = declaration
+            .methods
+            .iter()
+            .map(|method| {
+                let function = Function {
+                    declaration: Rc::clone(method),
+                    closure: Rc::clone(&closure),
+                    globals: Rc::clone(&self.globals),
+                    is_initializer: method.name.lexeme == "init",
+                };
+
+                (method.name.lexeme.to_owned(), Rc::new(function))
+            })
+            .collect();
+
+        let class = Class {
+            name: declaration.name.lexeme.to_owned(),
+            superclass,
+            methods,
+        };
+
+        self.environment
+            .define(&declaration.name.lexeme, Object::Class(Rc::new(class)));
+        Ok(Completion::Normal)
+    }
+
+    fn visit_throw_stmt(&mut self, keyword: &Token, value: &Expr) -> RloxResult<Completion> {
+        let value = self.evaluate(value)?;
+        Err(Error::Thrown(keyword.line, value))
+    }
+
+    fn visit_import_stmt(
+        &mut self,
+        keyword: &Token,
+        path: &str,
+        alias: &Token,
+    ) -> RloxResult<Completion> {
+        let module = self.import(keyword, path)?;
+        self.environment
+            .define(&alias.lexeme, Object::Module(module));
+        Ok(Completion::Normal)
+    }
+
+    fn visit_try_stmt(
+        &mut self,
+        body: &[Stmt],
+        catch: &Option<CatchClause>,
+        finally: &Option<Vec<Stmt>>,
+    ) -> RloxResult<Completion> {
+        let mut outcome = self.execute_block(body, Environment::from(&self.environment));
+
+        if let (Err(err), Some(catch)) = (&outcome, catch) {
+            if let Some(value) = self.exception_value(err) {
+                let catch_env = Environment::from(&self.environment);
+                catch_env.define(&catch.name.lexeme, value);
+                outcome = self.execute_block(&catch.body, catch_env);
+            }
+        }
+
+        // The finally block runs on every way out of the try statement. Leaving it early, by an
+        // error or a jump, overrides the pending outcome.
+        if let Some(finally) = finally {
+            match self.execute_block(finally, Environment::from(&self.environment))? {
+                Completion::Normal => {}
+                completion => return Ok(completion),
+            }
+        }
+
+        outcome
+    }
 }
@@ -1,24 +1,42 @@
 mod ast;
 mod environment;
-mod object;
+mod natives;
 
+pub mod bigint;
 pub mod interpreter;
+pub mod object;
 pub mod parser;
+pub mod rational;
+pub mod resolver;
 pub mod result;
 pub mod scanner;
 
 use interpreter::Interpreter;
 use parser::Parser;
-use result::RloxResult;
+use resolver::Resolver;
+use result::{RloxResult, Warning};
 use scanner::Scanner;
 use std::io::Write;
 
-pub fn evaluate<W: Write>(input: &str, out: W) -> RloxResult<()> {
+/// Evaluates the program, passing the warnings to `report` before it runs.
+pub fn evaluate<W: Write, F: FnMut(Warning)>(input: &str, out: W, report: F) -> RloxResult<()> {
+    let mut interpreter = Interpreter::new(out);
+    run(&mut interpreter, input, report)
+}
+
+/// Evaluates the program with an existing interpreter, for embedders that need to register
+/// natives beforehand or to keep the global state across programs. The warnings are passed to
+/// `report` before the program runs, so that they are reported even if it fails.
+pub fn run<W: Write, F: FnMut(Warning)>(
+    interpreter: &mut Interpreter<W>,
+    input: &str,
+    report: F,
+) -> RloxResult<()> {
     let tokens = Scanner::scan(input)?;
-    let statements = Parser::parse(tokens).map_err(|e| e[0].clone())?;
+    let (statements, warnings) = Parser::parse(tokens).map_err(|e| e[0].clone())?;
+    Resolver::resolve(&statements).map_err(|e| e[0].clone())?;
 
-    let mut interpreter = Interpreter::new(out);
-    interpreter.interpret(&statements)?;
+    warnings.into_iter().for_each(report);
 
-    Ok(())
+    interpreter.interpret(&statements)
 }
@@ -0,0 +1,31 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::object::Object;
+
+type Builtin = fn(&[Object]) -> Result<Object, String>;
+
+/// Natives defined in the global environment of every interpreter, with their arity.
+pub const BUILTINS: &[(&str, usize, Builtin)] = &[("clock", 0, clock), ("rational", 2, rational)];
+
+/// Returns the number of seconds elapsed since the Unix epoch.
+fn clock(_args: &[Object]) -> Result<Object, String> {
+    let elapsed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|_| String::from("System clock is set before the Unix epoch."))?;
+
+    Ok(Object::Number(elapsed.as_secs_f64()))
+}
+
+/// Returns the exact fraction of two integers or rationals.
+fn rational(args: &[Object]) -> Result<Object, String> {
+    let (numerator, denominator) = match (&args[0], &args[1]) {
+        (Object::Number(_), _) | (_, Object::Number(_)) => None,
+        (numerator, denominator) => numerator.to_exact().zip(denominator.to_exact()),
+    }
+    .ok_or_else(|| String::from("Arguments must be integers or rationals."))?;
+
+    numerator
+        .checked_div(&denominator)
+        .map(Object::from_exact)
+        .ok_or_else(|| String::from("Denominator can't be zero."))
+}
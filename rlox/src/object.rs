@@ -1,25 +1,415 @@
+use std::cell::RefCell;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::rc::Rc;
 use std::{cmp, fmt};
 
-use crate::ast::LiteralValue;
+use crate::ast::{FunctionDecl, LiteralValue};
+use crate::bigint::BigInt;
+use crate::environment::Environment;
+use crate::rational::Rational;
 
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub enum Object {
-    Number(f32),
+    Integer(i64),
+    // Integers that don't fit in an i64.
+    BigInt(Rc<BigInt>),
+    // Fractions that aren't integers.
+    Rational(Rc<Rational>),
+    Number(f64),
     String(String),
     True,
     False,
     Nil,
+    Function(Rc<Function>),
+    Class(Rc<Class>),
+    Instance(Rc<Instance>),
+    Native(Rc<Native>),
+    Module(Rc<Module>),
+    List(Rc<RefCell<Vec<Object>>>),
+    Map(Rc<RefCell<Map>>),
+    Range(Range),
+    Tuple(Rc<Vec<Object>>),
+}
+
+/// Integers from `start` to `end`, which is excluded unless the range is inclusive.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Range {
+    pub start: i64,
+    pub end: i64,
+    pub inclusive: bool,
+}
+
+/// Signature of the Rust functions callable from Lox. Errors are reported as runtime errors at
+/// the call site.
+pub type NativeFn = dyn Fn(&[Object]) -> Result<Object, String>;
+
+/// Function implemented in Rust.
+pub struct Native {
+    pub name: String,
+    pub arity: usize,
+    pub function: Box<NativeFn>,
+}
+
+impl fmt::Debug for Native {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Native({})", self.name)
+    }
+}
+
+/// File loaded by an `import` statement, its top-level bindings are read as properties.
+pub struct Module {
+    pub path: String,
+    pub globals: Rc<Environment>,
+}
+
+impl fmt::Debug for Module {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Module({})", self.path)
+    }
+}
+
+/// User-defined function, along with the environment it closes over.
+pub struct Function {
+    pub declaration: Rc<FunctionDecl>,
+    pub closure: Rc<Environment>,
+    // Globals of the module declaring the function, unresolved variables are looked up there.
+    pub globals: Rc<Environment>,
+    pub is_initializer: bool,
+}
+
+impl Function {
+    pub fn arity(&self) -> usize {
+        self.declaration.params.len()
+    }
+
+    /// Returns a copy of the method with `this` bound to the instance.
+    pub fn bind(&self, instance: Rc<Instance>) -> Function {
+        let env = Environment::from(&self.closure);
+        env.define("this", Object::Instance(instance));
+
+        Function {
+            declaration: Rc::clone(&self.declaration),
+            closure: env,
+            globals: Rc::clone(&self.globals),
+            is_initializer: self.is_initializer,
+        }
+    }
+}
+
+// The closure environment usually holds the function itself, it's left out to avoid infinite
+// recursion.
+impl fmt::Debug for Function {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Function({})", self.declaration.name.lexeme)
+    }
 }
 
 impl Object {
     pub fn is_truthy(&self) -> bool {
         !matches!(self, Object::Nil | Object::False)
     }
+
+    pub fn is_number(&self) -> bool {
+        matches!(
+            self,
+            Object::Integer(_) | Object::BigInt(_) | Object::Rational(_) | Object::Number(_)
+        )
+    }
+
+    /// Exact value of a number, `None` for other values and for infinite or NaN floats.
+    pub fn to_exact(&self) -> Option<Rational> {
+        match self {
+            Object::Integer(value) => Some(Rational::from_integer(BigInt::from(*value))),
+            Object::BigInt(value) => Some(Rational::from_integer(BigInt::clone(value))),
+            Object::Rational(value) => Some(Rational::clone(value)),
+            Object::Number(value) => Rational::from_f64(*value),
+            _ => None,
+        }
+    }
+
+    /// Builds the number with the narrowest representation holding the value.
+    pub fn from_exact(value: Rational) -> Object {
+        if !value.is_integer() {
+            return Object::Rational(Rc::new(value));
+        }
+
+        match value.numerator().to_i64() {
+            Some(value) => Object::Integer(value),
+            None => Object::BigInt(Rc::new(value.numerator().clone())),
+        }
+    }
+
+    pub fn to_float(&self) -> Option<f64> {
+        match self {
+            Object::Integer(value) => Some(*value as f64),
+            Object::BigInt(value) => Some(value.to_f64()),
+            Object::Rational(value) => Some(value.to_f64()),
+            Object::Number(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Compares two numbers by their exact values, whatever their representation.
+    fn compare_numbers(&self, other: &Object) -> Option<cmp::Ordering> {
+        match (self, other) {
+            (Object::Integer(a), Object::Integer(b)) => a.partial_cmp(b),
+            (Object::Number(a), Object::Number(b)) => a.partial_cmp(b),
+            _ => match (self.to_exact(), other.to_exact()) {
+                (Some(a), Some(b)) => a.partial_cmp(&b),
+                // One of them is an infinite or NaN float.
+                _ => self.to_float()?.partial_cmp(&other.to_float()?),
+            },
+        }
+    }
+
+    /// Formats a number the way the reference implementations do: integral values have no
+    /// fraction and others get the shortest representation that reads back to the same value.
+    /// Magnitudes from 1e21 and below 1e-6 switch to exponent notation, such as `1e+21`.
+    fn fmt_number(value: f64, f: &mut fmt::Formatter) -> fmt::Result {
+        match value {
+            f64::INFINITY => write!(f, "Infinity"),
+            f64::NEG_INFINITY => write!(f, "-Infinity"),
+            _ if value != 0.0 && (value.abs() >= 1e21 || value.abs() < 1e-6) => {
+                let formatted = format!("{:e}", value);
+                match formatted.split_once('e') {
+                    Some((mantissa, exponent)) if !exponent.starts_with('-') => {
+                        write!(f, "{}e+{}", mantissa, exponent)
+                    }
+                    _ => write!(f, "{}", formatted),
+                }
+            }
+            _ => write!(f, "{}", value),
+        }
+    }
+
+    /// Formats a value nested in a collection, strings are quoted to tell them apart from other
+    /// values.
+    fn fmt_element(&self, f: &mut fmt::Formatter, visiting: &mut Vec<usize>) -> fmt::Result {
+        match self {
+            Object::String(value) => write!(f, "\"{}\"", value),
+            _ => self.fmt_nested(f, visiting),
+        }
+    }
+
+    /// Formats the value, `visiting` holds the addresses of the collections being formatted so
+    /// that a collection containing itself is elided rather than formatted forever.
+    fn fmt_nested(&self, f: &mut fmt::Formatter, visiting: &mut Vec<usize>) -> fmt::Result {
+        match self {
+            Object::Tuple(values) => {
+                write!(f, "(")?;
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    value.fmt_element(f, visiting)?;
+                }
+                // A single element tuple prints as its literal.
+                if values.len() == 1 {
+                    write!(f, ",")?;
+                }
+                write!(f, ")")
+            }
+            Object::List(list) => {
+                let address = Rc::as_ptr(list) as *const () as usize;
+                if visiting.contains(&address) {
+                    return write!(f, "[...]");
+                }
+
+                visiting.push(address);
+                write!(f, "[")?;
+                for (i, element) in list.borrow().iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    element.fmt_element(f, visiting)?;
+                }
+                visiting.pop();
+                write!(f, "]")
+            }
+            Object::Map(map) => {
+                let address = Rc::as_ptr(map) as *const () as usize;
+                if visiting.contains(&address) {
+                    return write!(f, "{{...}}");
+                }
+
+                visiting.push(address);
+                write!(f, "{{")?;
+                for (i, (key, value)) in map.borrow().iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    key.fmt_element(f, visiting)?;
+                    write!(f, ": ")?;
+                    value.fmt_element(f, visiting)?;
+                }
+                visiting.pop();
+                write!(f, "}}")
+            }
+            _ => write!(f, "{}", self),
+        }
+    }
+}
+
+/// Hashable form of a map key. Keys are equal exactly when their objects are equal: values are
+/// compared by content and reference types by identity.
+#[derive(Debug, PartialEq, Eq, Hash)]
+enum HashKey {
+    Nil,
+    Bool(bool),
+    // Integers, and floats holding an integral value in the integer range.
+    Integer(i64),
+    // Other finite numbers, by their exact value.
+    Exact(Rational),
+    // Infinite floats.
+    Number(u64),
+    String(String),
+    Range(i64, i64, bool),
+    Tuple(Vec<HashKey>),
+    // Address of the shared value. The map holds the key object, so it can't be reused.
+    Reference(usize),
+}
+
+/// Returns the integer equal to the float, if any.
+fn integral(value: f64) -> Option<i64> {
+    // The bounds are powers of two, so they convert exactly.
+    if value.fract() == 0.0 && value >= i64::MIN as f64 && value < i64::MAX as f64 {
+        Some(value as i64)
+    } else {
+        None
+    }
+}
+
+impl HashKey {
+    fn new(key: &Object) -> Result<Self, String> {
+        Ok(match key {
+            Object::Nil => HashKey::Nil,
+            Object::True => HashKey::Bool(true),
+            Object::False => HashKey::Bool(false),
+            Object::Integer(value) => HashKey::Integer(*value),
+            Object::Number(value) if value.is_nan() => {
+                return Err(String::from("Map key can't be NaN."))
+            }
+            Object::Number(value) if value.is_infinite() => HashKey::Number(value.to_bits()),
+            // Numbers are equal when their values are, whatever their representation, they must
+            // hash the same.
+            Object::Number(value) => match integral(*value) {
+                Some(value) => HashKey::Integer(value),
+                None => HashKey::Exact(key.to_exact().expect("finite float")),
+            },
+            Object::BigInt(_) | Object::Rational(_) => {
+                HashKey::Exact(key.to_exact().expect("exact number"))
+            }
+            Object::String(value) => HashKey::String(value.to_owned()),
+            Object::Range(range) => HashKey::Range(range.start, range.end, range.inclusive),
+            Object::Tuple(values) => {
+                HashKey::Tuple(values.iter().map(HashKey::new).collect::<Result<_, _>>()?)
+            }
+            Object::Function(value) => HashKey::Reference(Rc::as_ptr(value) as *const () as usize),
+            Object::Class(value) => HashKey::Reference(Rc::as_ptr(value) as *const () as usize),
+            Object::Instance(value) => HashKey::Reference(Rc::as_ptr(value) as *const () as usize),
+            Object::Native(value) => HashKey::Reference(Rc::as_ptr(value) as *const () as usize),
+            Object::Module(value) => HashKey::Reference(Rc::as_ptr(value) as *const () as usize),
+            Object::List(value) => HashKey::Reference(Rc::as_ptr(value) as *const () as usize),
+            Object::Map(value) => HashKey::Reference(Rc::as_ptr(value) as *const () as usize),
+        })
+    }
+}
+
+/// Map keeping its entries in insertion order, so that printing it is deterministic.
+#[derive(Debug, Default)]
+pub struct Map {
+    entries: Vec<(Object, Object)>,
+    indices: HashMap<HashKey, usize>,
+}
+
+impl Map {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, key: &Object) -> Result<Option<&Object>, String> {
+        let key = HashKey::new(key)?;
+
+        Ok(self.indices.get(&key).map(|&index| &self.entries[index].1))
+    }
+
+    /// Sets the value of the key, an existing key keeps its position.
+    pub fn insert(&mut self, key: Object, value: Object) -> Result<(), String> {
+        match self.indices.entry(HashKey::new(&key)?) {
+            Entry::Occupied(entry) => self.entries[*entry.get()].1 = value,
+            Entry::Vacant(entry) => {
+                entry.insert(self.entries.len());
+                self.entries.push((key, value));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&Object, &Object)> {
+        self.entries.iter().map(|(key, value)| (key, value))
+    }
+}
+
+#[derive(Debug)]
+pub struct Class {
+    pub name: String,
+    pub superclass: Option<Rc<Class>>,
+    pub methods: HashMap<String, Rc<Function>>,
+}
+
+impl Class {
+    pub fn find_method(&self, name: &str) -> Option<Rc<Function>> {
+        match self.methods.get(name) {
+            Some(method) => Some(Rc::clone(method)),
+            None => self
+                .superclass
+                .as_ref()
+                .and_then(|superclass| superclass.find_method(name)),
+        }
+    }
+
+    pub fn arity(&self) -> usize {
+        self.find_method("init")
+            .map_or(0, |initializer| initializer.arity())
+    }
+}
+
+pub struct Instance {
+    pub class: Rc<Class>,
+    pub fields: RefCell<HashMap<String, Object>>,
+}
+
+impl Instance {
+    pub fn new(class: Rc<Class>) -> Self {
+        Self {
+            class,
+            fields: RefCell::new(HashMap::new()),
+        }
+    }
+}
+
+// Fields can reference the instance itself, only the class is printed.
+impl fmt::Debug for Instance {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Instance({})", self.class.name)
+    }
 }
 
 impl From<&LiteralValue> for Object {
     fn from(value: &LiteralValue) -> Self {
         match value {
+            LiteralValue::Integer(value) => Object::Integer(*value),
+            LiteralValue::BigInt(value) => Object::BigInt(Rc::new(value.clone())),
             LiteralValue::Number(value) => Object::Number(*value),
             LiteralValue::String(value) => Object::String(String::from(value)),
             LiteralValue::True => Object::True,
@@ -38,14 +428,50 @@ impl From<bool> for Object {
     }
 }
 
+// Collections can contain themselves, they are shown in their display form, which elides cycles.
+impl fmt::Debug for Object {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Object::Integer(value) => f.debug_tuple("Integer").field(value).finish(),
+            Object::BigInt(value) => f.debug_tuple("BigInt").field(value).finish(),
+            Object::Rational(value) => f.debug_tuple("Rational").field(value).finish(),
+            Object::Number(value) => f.debug_tuple("Number").field(value).finish(),
+            Object::String(value) => f.debug_tuple("String").field(value).finish(),
+            Object::True => write!(f, "True"),
+            Object::False => write!(f, "False"),
+            Object::Nil => write!(f, "Nil"),
+            Object::Function(value) => f.debug_tuple("Function").field(value).finish(),
+            Object::Class(value) => f.debug_tuple("Class").field(value).finish(),
+            Object::Instance(value) => f.debug_tuple("Instance").field(value).finish(),
+            Object::Native(value) => f.debug_tuple("Native").field(value).finish(),
+            Object::Module(value) => f.debug_tuple("Module").field(value).finish(),
+            Object::Range(value) => f.debug_tuple("Range").field(value).finish(),
+            Object::Tuple(_) => write!(f, "Tuple({})", self),
+            Object::List(_) => write!(f, "List({})", self),
+            Object::Map(_) => write!(f, "Map({})", self),
+        }
+    }
+}
+
 impl fmt::Display for Object {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Object::Number(value) => write!(f, "{}", value),
+            Object::Integer(value) => write!(f, "{}", value),
+            Object::BigInt(value) => write!(f, "{}", value),
+            Object::Rational(value) => write!(f, "{}", value),
+            Object::Number(value) => Self::fmt_number(*value, f),
             Object::String(value) => write!(f, "{}", value),
             Object::True => write!(f, "true"),
             Object::False => write!(f, "false"),
             Object::Nil => write!(f, "nil"),
+            Object::Function(function) => write!(f, "<fn {}>", function.declaration.name.lexeme),
+            Object::Class(class) => write!(f, "{}", class.name),
+            Object::Instance(instance) => write!(f, "{} instance", instance.class.name),
+            Object::Native(native) => write!(f, "<native fn {}>", native.name),
+            Object::Module(module) => write!(f, "<module {}>", module.path),
+            Object::Range(range) if range.inclusive => write!(f, "{}..={}", range.start, range.end),
+            Object::Range(range) => write!(f, "{}..{}", range.start, range.end),
+            Object::Tuple(_) | Object::List(_) | Object::Map(_) => self.fmt_nested(f, &mut vec![]),
         }
     }
 }
@@ -56,8 +482,19 @@ impl cmp::PartialEq for Object {
             (Object::True, Object::True)
             | (Object::False, Object::False)
             | (Object::Nil, Object::Nil) => true,
-            (Object::Number(a), Object::Number(b)) if a.eq(b) => true,
+            (a, b) if a.is_number() && b.is_number() => {
+                a.compare_numbers(b) == Some(cmp::Ordering::Equal)
+            }
             (Object::String(a), Object::String(b)) if a.eq(b) => true,
+            (Object::Range(a), Object::Range(b)) => a == b,
+            (Object::Tuple(a), Object::Tuple(b)) => a == b,
+            (Object::Function(a), Object::Function(b)) => Rc::ptr_eq(a, b),
+            (Object::Class(a), Object::Class(b)) => Rc::ptr_eq(a, b),
+            (Object::Instance(a), Object::Instance(b)) => Rc::ptr_eq(a, b),
+            (Object::Native(a), Object::Native(b)) => Rc::ptr_eq(a, b),
+            (Object::Module(a), Object::Module(b)) => Rc::ptr_eq(a, b),
+            (Object::List(a), Object::List(b)) => Rc::ptr_eq(a, b),
+            (Object::Map(a), Object::Map(b)) => Rc::ptr_eq(a, b),
             _ => false,
         }
     }
@@ -69,8 +506,25 @@ impl cmp::PartialOrd for Object {
             (Object::True, Object::True)
             | (Object::False, Object::False)
             | (Object::Nil, Object::Nil) => Some(cmp::Ordering::Equal),
-            (Object::Number(a), Object::Number(b)) => a.partial_cmp(b),
+            (a, b) if a.is_number() && b.is_number() => a.compare_numbers(b),
             (Object::String(a), Object::String(b)) => a.partial_cmp(b),
+            (Object::Range(a), Object::Range(b)) if a == b => Some(cmp::Ordering::Equal),
+            (Object::Tuple(a), Object::Tuple(b)) if a == b => Some(cmp::Ordering::Equal),
+            (Object::Function(a), Object::Function(b)) if Rc::ptr_eq(a, b) => {
+                Some(cmp::Ordering::Equal)
+            }
+            (Object::Class(a), Object::Class(b)) if Rc::ptr_eq(a, b) => Some(cmp::Ordering::Equal),
+            (Object::Instance(a), Object::Instance(b)) if Rc::ptr_eq(a, b) => {
+                Some(cmp::Ordering::Equal)
+            }
+            (Object::Native(a), Object::Native(b)) if Rc::ptr_eq(a, b) => {
+                Some(cmp::Ordering::Equal)
+            }
+            (Object::Module(a), Object::Module(b)) if Rc::ptr_eq(a, b) => {
+                Some(cmp::Ordering::Equal)
+            }
+            (Object::List(a), Object::List(b)) if Rc::ptr_eq(a, b) => Some(cmp::Ordering::Equal),
+            (Object::Map(a), Object::Map(b)) if Rc::ptr_eq(a, b) => Some(cmp::Ordering::Equal),
             _ => None,
         }
     }
@@ -1,18 +1,30 @@
-use crate::ast::{BinaryOp, Expr, LiteralValue, Stmt, Token, TokenKind, UnaryOp};
-use crate::result::{Error, RloxResult};
+use std::rc::Rc;
+
+use crate::ast::{
+    BinaryOp, BindingPattern, CatchClause, ClassDecl, Expr, FunctionDecl, LiteralValue, LogicalOp,
+    MatchArm, Pattern, Stmt, Token, TokenKind, UnaryOp, VarRef,
+};
+use crate::result::{Error, RloxResult, Warning};
 
 pub struct Parser {
     tokens: Vec<Token>,
     current: usize,
     errors: Vec<Error>,
+    warnings: Vec<Warning>,
+    loop_depth: usize,
+    // Whether the statement being parsed is the body of a match arm, which ends at a `,`.
+    arm_body: bool,
 }
 
 impl Parser {
-    pub fn parse(tokens: Vec<Token>) -> Result<Vec<Stmt>, Vec<Error>> {
+    pub fn parse(tokens: Vec<Token>) -> Result<(Vec<Stmt>, Vec<Warning>), Vec<Error>> {
         let mut parser = Parser {
             tokens,
             current: 0,
             errors: vec![],
+            warnings: vec![],
+            loop_depth: 0,
+            arm_body: false,
         };
 
         let statements = parser.program();
@@ -20,7 +32,7 @@ impl Parser {
         if !parser.errors.is_empty() {
             Err(parser.errors)
         } else {
-            Ok(statements)
+            Ok((statements, parser.warnings))
         }
     }
 
@@ -46,11 +58,46 @@ impl Parser {
                 self.advance();
                 self.var_declaration()
             }
+            TokenKind::Const => {
+                self.advance();
+                self.const_declaration()
+            }
+            TokenKind::Function => {
+                self.advance();
+                Ok(Stmt::Function(self.function("function")?))
+            }
+            TokenKind::Class => {
+                self.advance();
+                self.class_declaration()
+            }
+            TokenKind::Import => self.import_declaration(),
             _ => self.statement(),
         }
     }
 
+    fn import_declaration(&mut self) -> RloxResult<Stmt> {
+        let keyword = self.consume(&TokenKind::Import, "Expected import")?.clone();
+        let path = match &self.peek().kind {
+            TokenKind::String(path) => path.clone(),
+            _ => return Err(self.err("Expected module path after 'import'.")),
+        };
+        self.advance();
+
+        self.consume(&TokenKind::As, "Expected 'as' after module path.")?;
+        let alias = match &self.peek().kind {
+            TokenKind::Identifier(_) => self.advance().clone(),
+            _ => return Err(self.err("Expected module alias.")),
+        };
+        self.consume(&TokenKind::Semicolon, "Expected ';' after import.")?;
+
+        Ok(Stmt::Import(keyword, path, alias))
+    }
+
     fn var_declaration(&mut self) -> RloxResult<Stmt> {
+        if self.peek().kind == TokenKind::LeftParen {
+            return self.var_destructure_declaration();
+        }
+
         // TODO: Clean this up.
         let peeked = self.peek();
         let name = match &peeked.kind {
@@ -63,7 +110,7 @@ impl Parser {
         let initializer = match self.peek().kind {
             TokenKind::Equal => {
                 self.advance();
-                Some(Box::new(self.expression()?))
+                Some(Box::new(self.assignment()?))
             }
             _ => None,
         };
@@ -76,10 +123,153 @@ impl Parser {
         Ok(Stmt::Var(name, initializer))
     }
 
+    fn var_destructure_declaration(&mut self) -> RloxResult<Stmt> {
+        let pattern = self.binding_pattern()?;
+        let equals = self
+            .consume(
+                &TokenKind::Equal,
+                "Expected '=' after destructuring pattern.",
+            )?
+            .clone();
+        let initializer = self.assignment()?;
+
+        self.consume(
+            &TokenKind::Semicolon,
+            "Expected ';' after variable declaration.",
+        )?;
+
+        Ok(Stmt::VarDestructure(pattern, equals, Box::new(initializer)))
+    }
+
+    /// Parses a tuple of names, which has the syntax of a tuple literal: a single element needs a
+    /// trailing comma.
+    fn binding_pattern(&mut self) -> RloxResult<BindingPattern> {
+        self.consume(&TokenKind::LeftParen, "Expected '(' before pattern.")?;
+
+        let mut elements = vec![self.binding_element()?];
+        if self.peek().kind != TokenKind::Coma {
+            return Err(self.err("Expected ',' after pattern element."));
+        }
+        while self.peek().kind == TokenKind::Coma {
+            self.advance();
+            if self.peek().kind == TokenKind::RightParen {
+                break;
+            }
+            elements.push(self.binding_element()?);
+        }
+        self.consume(&TokenKind::RightParen, "Expected ')' after pattern.")?;
+
+        Ok(BindingPattern::Tuple(elements))
+    }
+
+    fn binding_element(&mut self) -> RloxResult<BindingPattern> {
+        match &self.peek().kind {
+            TokenKind::Identifier(_) => Ok(BindingPattern::Name(self.advance().clone())),
+            TokenKind::LeftParen => self.binding_pattern(),
+            _ => Err(self.err("Expected variable name in pattern.")),
+        }
+    }
+
+    fn const_declaration(&mut self) -> RloxResult<Stmt> {
+        let name = match &self.peek().kind {
+            TokenKind::Identifier(_) => self.advance().clone(),
+            _ => return Err(self.err("Expected constant name.")),
+        };
+
+        self.consume(&TokenKind::Equal, "Expected '=' after constant name.")?;
+        let initializer = self.assignment()?;
+
+        self.consume(
+            &TokenKind::Semicolon,
+            "Expected ';' after constant declaration.",
+        )?;
+
+        Ok(Stmt::Const(name, Box::new(initializer)))
+    }
+
+    fn class_declaration(&mut self) -> RloxResult<Stmt> {
+        let name = match &self.peek().kind {
+            TokenKind::Identifier(_) => self.advance().clone(),
+            _ => return Err(self.err("Expected class name.")),
+        };
+
+        let superclass = match self.peek().kind {
+            TokenKind::Less => {
+                self.advance();
+                match &self.peek().kind {
+                    TokenKind::Identifier(_) => Some(VarRef::new(self.advance().clone())),
+                    _ => return Err(self.err("Expected superclass name.")),
+                }
+            }
+            _ => None,
+        };
+
+        self.consume(&TokenKind::LeftBrace, "Expected '{' before class body.")?;
+
+        let mut methods = vec![];
+        while self.peek().kind != TokenKind::RightBrace && !self.is_at_end() {
+            methods.push(self.function("method")?);
+        }
+
+        self.consume(&TokenKind::RightBrace, "Expected '}' after class body.")?;
+
+        Ok(Stmt::Class(ClassDecl {
+            name,
+            superclass,
+            methods,
+        }))
+    }
+
+    fn function(&mut self, kind: &str) -> RloxResult<Rc<FunctionDecl>> {
+        let name = match &self.peek().kind {
+            TokenKind::Identifier(_) => self.advance().clone(),
+            _ => return Err(self.err(&format!("Expected {} name.", kind))),
+        };
+
+        self.consume(&TokenKind::LeftParen, "Expected '(' after function name.")?;
+        let mut params = vec![];
+        if self.peek().kind != TokenKind::RightParen {
+            loop {
+                match &self.peek().kind {
+                    TokenKind::Identifier(_) => params.push(self.advance().clone()),
+                    _ => return Err(self.err("Expected parameter name.")),
+                }
+
+                if self.peek().kind != TokenKind::Coma {
+                    break;
+                }
+                self.advance();
+            }
+        }
+        self.consume(&TokenKind::RightParen, "Expected ')' after parameters.")?;
+        self.consume(&TokenKind::LeftBrace, "Expected '{' before function body.")?;
+
+        // Loop control flow doesn't cross function boundaries.
+        let loop_depth = self.loop_depth;
+        self.loop_depth = 0;
+        let body = self.block();
+        self.loop_depth = loop_depth;
+
+        Ok(Rc::new(FunctionDecl {
+            name,
+            params,
+            body: body?,
+        }))
+    }
+
     fn statement(&mut self) -> RloxResult<Stmt> {
         match self.peek().kind {
             TokenKind::Print => self.print_statement(),
-            TokenKind::LeftBrace => self.block_statement(),
+            TokenKind::If => self.if_statement(),
+            TokenKind::While => self.while_statement(),
+            TokenKind::For => self.for_statement(),
+            TokenKind::Break => self.break_statement(),
+            TokenKind::Continue => self.continue_statement(),
+            TokenKind::Return => self.return_statement(),
+            TokenKind::Match => self.match_statement(),
+            TokenKind::Throw => self.throw_statement(),
+            TokenKind::Try => self.try_statement(),
+            TokenKind::LeftBrace if !self.starts_map() => self.block_statement(),
             _ => self.expression_statement(),
         }
     }
@@ -88,47 +278,520 @@ impl Parser {
         // TODO: Invoker should consume if needed.
         self.consume(&TokenKind::Print, "Expected print")?;
         let expr = self.expression()?;
-        self.consume(&TokenKind::Semicolon, "Expected ';' after value")?;
+        self.end_statement("Expected ';' after value")?;
 
         Ok(Stmt::Print(Box::new(expr)))
     }
 
+    fn if_statement(&mut self) -> RloxResult<Stmt> {
+        self.consume(&TokenKind::If, "Expected if")?;
+        self.consume(&TokenKind::LeftParen, "Expected '(' after 'if'.")?;
+        let condition = self.expression()?;
+        self.consume(&TokenKind::RightParen, "Expected ')' after if condition.")?;
+
+        let then_branch = self.statement()?;
+        let else_branch = match self.peek().kind {
+            TokenKind::Else => {
+                self.advance();
+                Some(Box::new(self.statement()?))
+            }
+            _ => None,
+        };
+
+        Ok(Stmt::If(
+            Box::new(condition),
+            Box::new(then_branch),
+            else_branch,
+        ))
+    }
+
+    fn while_statement(&mut self) -> RloxResult<Stmt> {
+        self.consume(&TokenKind::While, "Expected while")?;
+        self.consume(&TokenKind::LeftParen, "Expected '(' after 'while'.")?;
+        let condition = self.expression()?;
+        self.consume(&TokenKind::RightParen, "Expected ')' after condition.")?;
+
+        let body = self.loop_body()?;
+        Ok(Stmt::While(Box::new(condition), Box::new(body), None))
+    }
+
+    fn for_statement(&mut self) -> RloxResult<Stmt> {
+        self.consume(&TokenKind::For, "Expected for")?;
+        self.consume(&TokenKind::LeftParen, "Expected '(' after 'for'.")?;
+
+        if self.starts_for_in() {
+            return self.for_in_statement();
+        }
+
+        let initializer = match self.peek().kind {
+            TokenKind::Semicolon => {
+                self.advance();
+                None
+            }
+            TokenKind::Var => {
+                self.advance();
+                Some(self.var_declaration()?)
+            }
+            _ => Some(self.expression_statement()?),
+        };
+
+        let condition = match self.peek().kind {
+            TokenKind::Semicolon => Expr::Literal(LiteralValue::True),
+            _ => self.expression()?,
+        };
+        self.consume(&TokenKind::Semicolon, "Expected ';' after loop condition.")?;
+
+        let increment = match self.peek().kind {
+            TokenKind::RightParen => None,
+            _ => Some(Box::new(self.expression()?)),
+        };
+        self.consume(&TokenKind::RightParen, "Expected ')' after for clauses.")?;
+
+        // The increment is kept on the while statement so that `continue` still runs it.
+        let body = self.loop_body()?;
+        let mut statement = Stmt::While(Box::new(condition), Box::new(body), increment);
+
+        if let Some(initializer) = initializer {
+            statement = Stmt::Block(vec![initializer, statement]);
+        }
+
+        Ok(statement)
+    }
+
+    fn for_in_statement(&mut self) -> RloxResult<Stmt> {
+        let name = self.advance().clone();
+        self.consume(&TokenKind::In, "Expected 'in' after loop variable.")?;
+        let iterable = self.expression()?;
+        self.consume(&TokenKind::RightParen, "Expected ')' after iterated value.")?;
+
+        let body = self.loop_body()?;
+        Ok(Stmt::ForIn(name, Box::new(iterable), Box::new(body)))
+    }
+
+    fn loop_body(&mut self) -> RloxResult<Stmt> {
+        self.loop_depth += 1;
+        let body = self.statement();
+        self.loop_depth -= 1;
+
+        body
+    }
+
+    fn break_statement(&mut self) -> RloxResult<Stmt> {
+        self.consume(&TokenKind::Break, "Expected break")?;
+        if self.loop_depth == 0 {
+            return Err(self.err("Can't use 'break' outside of a loop."));
+        }
+        self.end_statement("Expected ';' after 'break'.")?;
+
+        Ok(Stmt::Break)
+    }
+
+    fn continue_statement(&mut self) -> RloxResult<Stmt> {
+        self.consume(&TokenKind::Continue, "Expected continue")?;
+        if self.loop_depth == 0 {
+            return Err(self.err("Can't use 'continue' outside of a loop."));
+        }
+        self.end_statement("Expected ';' after 'continue'.")?;
+
+        Ok(Stmt::Continue)
+    }
+
+    fn return_statement(&mut self) -> RloxResult<Stmt> {
+        let keyword = self.consume(&TokenKind::Return, "Expected return")?.clone();
+
+        let value = match self.peek().kind {
+            TokenKind::Semicolon => None,
+            TokenKind::Coma | TokenKind::RightBrace if self.arm_body => None,
+            _ => Some(Box::new(self.expression()?)),
+        };
+        self.end_statement("Expected ';' after return value.")?;
+
+        Ok(Stmt::Return(keyword, value))
+    }
+
+    fn throw_statement(&mut self) -> RloxResult<Stmt> {
+        let keyword = self.consume(&TokenKind::Throw, "Expected throw")?.clone();
+        let value = self.expression()?;
+        self.end_statement("Expected ';' after thrown value.")?;
+
+        Ok(Stmt::Throw(keyword, Box::new(value)))
+    }
+
+    fn try_statement(&mut self) -> RloxResult<Stmt> {
+        self.consume(&TokenKind::Try, "Expected try")?;
+        self.consume(&TokenKind::LeftBrace, "Expected '{' after 'try'.")?;
+        let body = self.block()?;
+
+        let catch = match self.peek().kind {
+            TokenKind::Catch => {
+                self.advance();
+                self.consume(&TokenKind::LeftParen, "Expected '(' after 'catch'.")?;
+                let name = match &self.peek().kind {
+                    TokenKind::Identifier(_) => self.advance().clone(),
+                    _ => return Err(self.err("Expected exception variable name.")),
+                };
+                self.consume(
+                    &TokenKind::RightParen,
+                    "Expected ')' after exception variable.",
+                )?;
+                self.consume(&TokenKind::LeftBrace, "Expected '{' before catch body.")?;
+
+                Some(CatchClause {
+                    name,
+                    body: self.block()?,
+                })
+            }
+            _ => None,
+        };
+
+        let finally = match self.peek().kind {
+            TokenKind::Finally => {
+                self.advance();
+                self.consume(&TokenKind::LeftBrace, "Expected '{' after 'finally'.")?;
+                Some(self.block()?)
+            }
+            _ => None,
+        };
+
+        if catch.is_none() && finally.is_none() {
+            return Err(self.err("Expected 'catch' or 'finally' after try block."));
+        }
+
+        Ok(Stmt::Try(body, catch, finally))
+    }
+
+    fn match_statement(&mut self) -> RloxResult<Stmt> {
+        let keyword = self.consume(&TokenKind::Match, "Expected match")?.clone();
+        self.consume(&TokenKind::LeftParen, "Expected '(' after 'match'.")?;
+        let subject = self.expression()?;
+        self.consume(&TokenKind::RightParen, "Expected ')' after match value.")?;
+        self.consume(&TokenKind::LeftBrace, "Expected '{' before match arms.")?;
+
+        let mut arms = vec![];
+        let mut exhaustive = false;
+
+        while self.peek().kind != TokenKind::RightBrace && !self.is_at_end() {
+            if exhaustive {
+                self.warnings.push(Warning(
+                    self.peek().line,
+                    String::from("Unreachable match arm."),
+                ));
+            }
+
+            let arm = self.match_arm()?;
+            if arm.guard.is_none() && matches!(arm.pattern, Pattern::Wildcard | Pattern::Binding(_))
+            {
+                exhaustive = true;
+            }
+            arms.push(arm);
+
+            if self.peek().kind == TokenKind::Coma {
+                self.advance();
+            }
+        }
+
+        self.consume(&TokenKind::RightBrace, "Expected '}' after match arms.")?;
+        Ok(Stmt::Match(keyword, Box::new(subject), arms))
+    }
+
+    fn match_arm(&mut self) -> RloxResult<MatchArm> {
+        let pattern = self.pattern()?;
+
+        let guard = match self.peek().kind {
+            TokenKind::If => {
+                self.advance();
+                Some(self.expression()?)
+            }
+            _ => None,
+        };
+
+        self.consume(&TokenKind::FatArrow, "Expected '=>' after match pattern.")?;
+        let arm_body = self.arm_body;
+        self.arm_body = true;
+        let body = self.statement();
+        self.arm_body = arm_body;
+        let body = body?;
+
+        Ok(MatchArm {
+            pattern,
+            guard,
+            body,
+        })
+    }
+
+    fn pattern(&mut self) -> RloxResult<Pattern> {
+        match &self.peek().kind {
+            TokenKind::Identifier(name) if name == "_" => {
+                self.advance();
+                Ok(Pattern::Wildcard)
+            }
+            TokenKind::Identifier(_) => Ok(Pattern::Binding(self.advance().clone())),
+            TokenKind::Integer(_) | TokenKind::Number(_) | TokenKind::Minus => {
+                let start = self.pattern_number()?;
+
+                let inclusive = match self.peek().kind {
+                    TokenKind::DotDot => false,
+                    TokenKind::DotDotEqual => true,
+                    _ => return Ok(Pattern::Literal(start)),
+                };
+                self.advance();
+
+                let end = self.pattern_number()?;
+                Ok(Pattern::Range(start, end, inclusive))
+            }
+            TokenKind::String(value) => {
+                let value = String::from(value);
+                self.advance();
+                Ok(Pattern::Literal(LiteralValue::String(value)))
+            }
+            TokenKind::True => {
+                self.advance();
+                Ok(Pattern::Literal(LiteralValue::True))
+            }
+            TokenKind::False => {
+                self.advance();
+                Ok(Pattern::Literal(LiteralValue::False))
+            }
+            TokenKind::Nil => {
+                self.advance();
+                Ok(Pattern::Literal(LiteralValue::Nil))
+            }
+            _ => Err(self.err("Expected pattern.")),
+        }
+    }
+
+    fn pattern_number(&mut self) -> RloxResult<LiteralValue> {
+        let negative = self.peek().kind == TokenKind::Minus;
+        if negative {
+            self.advance();
+        }
+
+        match self.peek().kind {
+            TokenKind::Integer(value) => {
+                self.advance();
+                Ok(LiteralValue::Integer(if negative { -value } else { value }))
+            }
+            TokenKind::Number(value) => {
+                self.advance();
+                Ok(LiteralValue::Number(if negative { -value } else { value }))
+            }
+            _ => Err(self.err("Expected number in pattern.")),
+        }
+    }
+
     fn block_statement(&mut self) -> RloxResult<Stmt> {
         // TODO: Invoker should consume if needed.
         self.consume(&TokenKind::LeftBrace, "Expected left bace")?;
 
+        let statements = self.block()?;
+        Ok(Stmt::Block(statements))
+    }
+
+    fn block(&mut self) -> RloxResult<Vec<Stmt>> {
+        let arm_body = self.arm_body;
+        self.arm_body = false;
+        let statements = self.block_statements();
+        self.arm_body = arm_body;
+
+        statements
+    }
+
+    fn block_statements(&mut self) -> RloxResult<Vec<Stmt>> {
         let mut statements = vec![];
         while self.peek().kind != TokenKind::RightBrace && !self.is_at_end() {
             statements.push(self.declaration()?);
         }
 
         self.consume(&TokenKind::RightBrace, "Expected '}' after block statement")?;
-        Ok(Stmt::Block(statements))
+        Ok(statements)
     }
 
     fn expression_statement(&mut self) -> RloxResult<Stmt> {
         let expr = self.expression()?;
-        self.consume(&TokenKind::Semicolon, "Expected ';' after expression")?;
+        self.end_statement("Expected ';' after expression")?;
 
         Ok(Stmt::Expression(Box::new(expr)))
     }
 
+    /// Consumes the `;` ending a simple statement. The body of a match arm can also end at the `,`
+    /// before the next arm or at the `}` after the last one.
+    fn end_statement(&mut self, msg: &'static str) -> RloxResult<()> {
+        let arm_end = matches!(self.peek().kind, TokenKind::Coma | TokenKind::RightBrace);
+        if !(self.arm_body && arm_end) {
+            self.consume(&TokenKind::Semicolon, msg)?;
+        }
+
+        Ok(())
+    }
+
     fn expression(&mut self) -> RloxResult<Expr> {
-        self.assignment()
+        self.comma()
     }
 
-    fn assignment(&mut self) -> RloxResult<Expr> {
-        let expr = self.equality()?;
+    fn comma(&mut self) -> RloxResult<Expr> {
+        let mut expr = self.assignment()?;
 
-        if TokenKind::Equal == self.peek().kind {
+        // The comma after the body of a match arm separates it from the next arm.
+        while self.peek().kind == TokenKind::Coma && !self.arm_body {
             self.advance();
-            let value = self.assignment()?;
 
-            if let Expr::Variable(id) = expr {
-                return Ok(Expr::Assign(id, Box::new(value)));
+            let right = self.assignment()?;
+            expr = Expr::Comma(Box::new(expr), Box::new(right));
+        }
+
+        Ok(expr)
+    }
+
+    fn assignment(&mut self) -> RloxResult<Expr> {
+        let expr = self.conditional()?;
+
+        match self.peek().kind {
+            TokenKind::Equal => {
+                let equals = self.advance().clone();
+                let value = self.assignment()?;
+
+                match expr {
+                    Expr::Tuple(_) => {
+                        self.destructuring_target(&expr)?;
+                        Ok(Expr::DestructureAssign(
+                            Box::new(expr),
+                            equals,
+                            Box::new(value),
+                        ))
+                    }
+                    Expr::Variable(var) => Ok(Expr::Assign(var, Box::new(value))),
+                    Expr::Get(object, name) => Ok(Expr::Set(object, name, Box::new(value))),
+                    Expr::Index(object, bracket, index) => {
+                        Ok(Expr::IndexSet(object, bracket, index, Box::new(value)))
+                    }
+                    _ => Err(self.err("Invalid assignment target.")),
+                }
+            }
+            TokenKind::QuestionQuestionEqual => {
+                self.advance();
+                let target = self.assignment_target(expr)?;
+                let value = self.assignment()?;
+
+                Ok(Expr::NilCoalesceAssign(Box::new(target), Box::new(value)))
             }
+            TokenKind::PlusEqual
+            | TokenKind::MinusEqual
+            | TokenKind::StarEqual
+            | TokenKind::SlashEqual => {
+                let token = self.advance().clone();
+                let operator = match token.kind {
+                    TokenKind::PlusEqual => BinaryOp::Plus,
+                    TokenKind::MinusEqual => BinaryOp::Minus,
+                    TokenKind::StarEqual => BinaryOp::Star,
+                    _ => BinaryOp::Slash,
+                };
+                let target = self.assignment_target(expr)?;
+                let value = self.assignment()?;
+
+                Ok(Expr::CompoundAssign(
+                    Box::new(target),
+                    token,
+                    operator,
+                    Box::new(value),
+                    false,
+                ))
+            }
+            _ => Ok(expr),
+        }
+    }
+
+    /// Checks that the expression designates a storage location, for assignments that read the
+    /// target before writing it.
+    fn assignment_target(&self, expr: Expr) -> RloxResult<Expr> {
+        match expr {
+            Expr::Variable(_) | Expr::Get(_, _) | Expr::Index(_, _, _) => Ok(expr),
+            _ => Err(self.err("Invalid assignment target.")),
+        }
+    }
+
+    /// Checks that the tuple only holds assignment targets, at any depth.
+    fn destructuring_target(&self, expr: &Expr) -> RloxResult<()> {
+        match expr {
+            Expr::Tuple(elements) => elements
+                .iter()
+                .try_for_each(|element| self.destructuring_target(element)),
+            Expr::Variable(_) | Expr::Get(_, _) | Expr::Index(_, _, _) => Ok(()),
+            _ => Err(self.err("Invalid assignment target.")),
+        }
+    }
+
+    /// Builds `++` and `--`, which add or subtract one from their target.
+    fn increment(&self, token: Token, target: Expr, postfix: bool) -> RloxResult<Expr> {
+        let operator = match token.kind {
+            TokenKind::PlusPlus => BinaryOp::Plus,
+            _ => BinaryOp::Minus,
+        };
+
+        Ok(Expr::CompoundAssign(
+            Box::new(self.assignment_target(target)?),
+            token,
+            operator,
+            Box::new(Expr::Literal(LiteralValue::Integer(1))),
+            postfix,
+        ))
+    }
+
+    fn conditional(&mut self) -> RloxResult<Expr> {
+        let condition = self.nil_coalesce()?;
+
+        if self.peek().kind != TokenKind::Question {
+            return Ok(condition);
+        }
+        self.advance();
+
+        let then_branch = self.assignment()?;
+        self.consume(
+            &TokenKind::Colon,
+            "Expected ':' after then branch of conditional expression.",
+        )?;
+        let else_branch = self.conditional()?;
+
+        Ok(Expr::Conditional(
+            Box::new(condition),
+            Box::new(then_branch),
+            Box::new(else_branch),
+        ))
+    }
+
+    fn nil_coalesce(&mut self) -> RloxResult<Expr> {
+        let mut expr = self.or()?;
+
+        while self.peek().kind == TokenKind::QuestionQuestion {
+            self.advance();
+
+            let right = self.or()?;
+            expr = Expr::Logical(Box::new(expr), LogicalOp::NilCoalesce, Box::new(right));
+        }
+
+        Ok(expr)
+    }
 
-            return Err(self.err("Invalid assignment target."));
+    fn or(&mut self) -> RloxResult<Expr> {
+        let mut expr = self.and()?;
+
+        while self.peek().kind == TokenKind::Or {
+            self.advance();
+
+            let right = self.and()?;
+            expr = Expr::Logical(Box::new(expr), LogicalOp::Or, Box::new(right));
+        }
+
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> RloxResult<Expr> {
+        let mut expr = self.equality()?;
+
+        while self.peek().kind == TokenKind::And {
+            self.advance();
+
+            let right = self.equality()?;
+            expr = Expr::Logical(Box::new(expr), LogicalOp::And, Box::new(right));
         }
 
         Ok(expr)
@@ -145,10 +808,10 @@ impl Parser {
             };
 
             if let Some(operator) = operator {
-                self.advance();
+                let token = self.advance().clone();
 
                 let right = self.comparison()?;
-                expr = Expr::Binary(Box::new(expr), operator, Box::new(right));
+                expr = Expr::Binary(Box::new(expr), token, operator, Box::new(right));
             } else {
                 break;
             }
@@ -158,7 +821,7 @@ impl Parser {
     }
 
     fn comparison(&mut self) -> RloxResult<Expr> {
-        let mut expr = self.term()?;
+        let mut expr = self.range()?;
 
         while !self.is_at_end() {
             let operator = match self.peek().kind {
@@ -170,10 +833,119 @@ impl Parser {
             };
 
             if let Some(operator) = operator {
-                self.advance();
+                let token = self.advance().clone();
+
+                let right = self.range()?;
+                expr = Expr::Binary(Box::new(expr), token, operator, Box::new(right));
+            } else {
+                break;
+            }
+        }
+
+        Ok(expr)
+    }
+
+    /// Ranges don't chain, `a..b..c` is an error.
+    fn range(&mut self) -> RloxResult<Expr> {
+        let start = self.bit_or()?;
+
+        let inclusive = match self.peek().kind {
+            TokenKind::DotDot => false,
+            TokenKind::DotDotEqual => true,
+            _ => return Ok(start),
+        };
+        let token = self.advance().clone();
+
+        let end = self.bit_or()?;
+        Ok(Expr::Range(
+            Box::new(start),
+            token,
+            Box::new(end),
+            inclusive,
+        ))
+    }
+
+    fn bit_or(&mut self) -> RloxResult<Expr> {
+        let mut expr = self.bit_xor()?;
+
+        while !self.is_at_end() {
+            let operator = match self.peek().kind {
+                TokenKind::Pipe => Some(BinaryOp::Pipe),
+                _ => None,
+            };
+
+            if let Some(operator) = operator {
+                let token = self.advance().clone();
+
+                let right = self.bit_xor()?;
+                expr = Expr::Binary(Box::new(expr), token, operator, Box::new(right));
+            } else {
+                break;
+            }
+        }
+
+        Ok(expr)
+    }
+
+    fn bit_xor(&mut self) -> RloxResult<Expr> {
+        let mut expr = self.bit_and()?;
+
+        while !self.is_at_end() {
+            let operator = match self.peek().kind {
+                TokenKind::Caret => Some(BinaryOp::Caret),
+                _ => None,
+            };
+
+            if let Some(operator) = operator {
+                let token = self.advance().clone();
+
+                let right = self.bit_and()?;
+                expr = Expr::Binary(Box::new(expr), token, operator, Box::new(right));
+            } else {
+                break;
+            }
+        }
+
+        Ok(expr)
+    }
+
+    fn bit_and(&mut self) -> RloxResult<Expr> {
+        let mut expr = self.shift()?;
+
+        while !self.is_at_end() {
+            let operator = match self.peek().kind {
+                TokenKind::Ampersand => Some(BinaryOp::Ampersand),
+                _ => None,
+            };
+
+            if let Some(operator) = operator {
+                let token = self.advance().clone();
+
+                let right = self.shift()?;
+                expr = Expr::Binary(Box::new(expr), token, operator, Box::new(right));
+            } else {
+                break;
+            }
+        }
+
+        Ok(expr)
+    }
+
+    fn shift(&mut self) -> RloxResult<Expr> {
+        let mut expr = self.term()?;
+
+        while !self.is_at_end() {
+            let operator = match self.peek().kind {
+                TokenKind::LessLess => Some(BinaryOp::LessLess),
+                TokenKind::GreaterGreater => Some(BinaryOp::GreaterGreater),
+                _ => None,
+            };
+
+            if let Some(operator) = operator {
+                let token = self.advance().clone();
 
                 let right = self.term()?;
-                expr = Expr::Binary(Box::new(expr), operator, Box::new(right));
+                expr = Expr::Binary(Box::new(expr), token, operator, Box::new(right));
             } else {
                 break;
             }
@@ -193,10 +965,10 @@ impl Parser {
             };
 
             if let Some(operator) = operator {
-                self.advance();
+                let token = self.advance().clone();
 
                 let right = self.factor()?;
-                expr = Expr::Binary(Box::new(expr), operator, Box::new(right));
+                expr = Expr::Binary(Box::new(expr), token, operator, Box::new(right));
             } else {
                 break;
             }
@@ -211,15 +983,17 @@ impl Parser {
         while !self.is_at_end() {
             let operator = match self.peek().kind {
                 TokenKind::Slash => Some(BinaryOp::Slash),
+                TokenKind::TildeSlash => Some(BinaryOp::TildeSlash),
+                TokenKind::Percent => Some(BinaryOp::Percent),
                 TokenKind::Star => Some(BinaryOp::Star),
                 _ => None,
             };
 
             if let Some(operator) = operator {
-                self.advance();
+                let token = self.advance().clone();
 
                 let right = self.unary()?;
-                expr = Expr::Binary(Box::new(expr), operator, Box::new(right));
+                expr = Expr::Binary(Box::new(expr), token, operator, Box::new(right));
             } else {
                 break;
             }
@@ -229,40 +1003,219 @@ impl Parser {
     }
 
     fn unary(&mut self) -> RloxResult<Expr> {
+        if let TokenKind::PlusPlus | TokenKind::MinusMinus = self.peek().kind {
+            let operator = self.advance().clone();
+            let target = self.unary()?;
+
+            return self.increment(operator, target, false);
+        }
+
         let operator = match self.peek().kind {
             TokenKind::Bang => Some(UnaryOp::Not),
             TokenKind::Minus => Some(UnaryOp::Neg),
+            TokenKind::Tilde => Some(UnaryOp::BitNot),
             _ => None,
         };
 
         if let Some(operator) = operator {
-            self.advance();
+            let token = self.advance().clone();
 
             let unary = self.unary()?;
-            Ok(Expr::Unary(operator, Box::new(unary)))
+            Ok(Expr::Unary(token, operator, Box::new(unary)))
         } else {
-            self.primary()
+            self.power()
+        }
+    }
+
+    /// Exponentiation binds tighter than unary operators on its left, `-2 ** 2` is `-(2 ** 2)`,
+    /// and is right-associative.
+    fn power(&mut self) -> RloxResult<Expr> {
+        let expr = self.postfix()?;
+
+        if self.peek().kind == TokenKind::StarStar {
+            let token = self.advance().clone();
+
+            let right = self.unary()?;
+            return Ok(Expr::Binary(
+                Box::new(expr),
+                token,
+                BinaryOp::StarStar,
+                Box::new(right),
+            ));
         }
+
+        Ok(expr)
+    }
+
+    fn postfix(&mut self) -> RloxResult<Expr> {
+        let expr = self.call()?;
+
+        if let TokenKind::PlusPlus | TokenKind::MinusMinus = self.peek().kind {
+            let operator = self.advance().clone();
+            return self.increment(operator, expr, true);
+        }
+
+        Ok(expr)
+    }
+
+    fn call(&mut self) -> RloxResult<Expr> {
+        let mut expr = self.primary()?;
+
+        loop {
+            match self.peek().kind {
+                TokenKind::LeftParen => {
+                    self.advance();
+                    expr = self.finish_call(expr)?;
+                }
+                TokenKind::Dot => {
+                    self.advance();
+                    let name = match &self.peek().kind {
+                        TokenKind::Identifier(_) => self.advance().clone(),
+                        _ => return Err(self.err("Expected property name after '.'.")),
+                    };
+                    expr = Expr::Get(Box::new(expr), name);
+                }
+                TokenKind::LeftBracket => {
+                    self.advance();
+                    let index = self.expression()?;
+                    let bracket = self
+                        .consume(&TokenKind::RightBracket, "Expected ']' after index.")?
+                        .clone();
+                    expr = Expr::Index(Box::new(expr), bracket, Box::new(index));
+                }
+                _ => break,
+            }
+        }
+
+        Ok(expr)
+    }
+
+    fn finish_call(&mut self, callee: Expr) -> RloxResult<Expr> {
+        let mut args = vec![];
+        if self.peek().kind != TokenKind::RightParen {
+            loop {
+                args.push(self.assignment()?);
+
+                if self.peek().kind != TokenKind::Coma {
+                    break;
+                }
+                self.advance();
+            }
+        }
+
+        let paren = self
+            .consume(&TokenKind::RightParen, "Expected ')' after arguments.")?
+            .clone();
+        Ok(Expr::Call(Box::new(callee), paren, args))
     }
 
     fn primary(&mut self) -> RloxResult<Expr> {
         let token = self.advance();
         match &token.kind {
+            TokenKind::Integer(value) => Ok(Expr::Literal(LiteralValue::Integer(*value))),
+            TokenKind::BigInt(value) => Ok(Expr::Literal(LiteralValue::BigInt(value.clone()))),
             TokenKind::Number(value) => Ok(Expr::Literal(LiteralValue::Number(*value))),
             TokenKind::String(value) => {
                 Ok(Expr::Literal(LiteralValue::String(String::from(value))))
             }
+            TokenKind::Interpolation(value) => {
+                let mut parts = vec![Expr::Literal(LiteralValue::String(String::from(value)))];
+                loop {
+                    parts.push(self.expression()?);
+
+                    match &self.peek().kind {
+                        TokenKind::InterpolationMiddle(value) => {
+                            parts.push(Expr::Literal(LiteralValue::String(String::from(value))));
+                            self.advance();
+                        }
+                        TokenKind::InterpolationEnd(value) => {
+                            parts.push(Expr::Literal(LiteralValue::String(String::from(value))));
+                            self.advance();
+                            break;
+                        }
+                        _ => return Err(self.err("Expected '}' after interpolated expression.")),
+                    }
+                }
+
+                Ok(Expr::Interpolation(parts))
+            }
             TokenKind::True => Ok(Expr::Literal(LiteralValue::True)),
             TokenKind::False => Ok(Expr::Literal(LiteralValue::False)),
             TokenKind::Nil => Ok(Expr::Literal(LiteralValue::Nil)),
-            TokenKind::Identifier(_) => Ok(Expr::Variable(token.clone())),
+            TokenKind::Identifier(_) => Ok(Expr::Variable(VarRef::new(token.clone()))),
+            TokenKind::This => Ok(Expr::This(VarRef::new(token.clone()))),
+            TokenKind::Super => {
+                let keyword = VarRef::new(token.clone());
+                self.consume(&TokenKind::Dot, "Expected '.' after 'super'.")?;
+                let method = match &self.peek().kind {
+                    TokenKind::Identifier(_) => self.advance().clone(),
+                    _ => return Err(self.err("Expected superclass method name.")),
+                };
+
+                Ok(Expr::Super(keyword, method))
+            }
+            // A comma makes a tuple rather than a comma expression, `(a,)` has a single element.
             TokenKind::LeftParen => {
-                let expr = self.expression()?;
-                self.consume(&TokenKind::RightParen, "Expected ')' after expression.")?;
+                let expr = self.assignment()?;
+                if self.peek().kind != TokenKind::Coma {
+                    self.consume(&TokenKind::RightParen, "Expected ')' after expression.")?;
+                    return Ok(Expr::Grouping(Box::new(expr)));
+                }
+
+                let mut elements = vec![expr];
+                while self.peek().kind == TokenKind::Coma {
+                    self.advance();
+                    if self.peek().kind == TokenKind::RightParen {
+                        break;
+                    }
+                    elements.push(self.assignment()?);
+                }
+                self.consume(&TokenKind::RightParen, "Expected ')' after tuple elements.")?;
+
+                Ok(Expr::Tuple(elements))
+            }
+            TokenKind::LeftBracket => {
+                let mut elements = vec![];
+                while self.peek().kind != TokenKind::RightBracket && !self.is_at_end() {
+                    elements.push(self.assignment()?);
+
+                    if self.peek().kind != TokenKind::Coma {
+                        break;
+                    }
+                    self.advance();
+                }
+
+                self.consume(
+                    &TokenKind::RightBracket,
+                    "Expected ']' after list elements.",
+                )?;
+                Ok(Expr::List(elements))
+            }
+            TokenKind::LeftBrace => {
+                let brace = token.clone();
+                let mut entries = vec![];
+                while self.peek().kind != TokenKind::RightBrace && !self.is_at_end() {
+                    let key = self.assignment()?;
+                    self.consume(&TokenKind::Colon, "Expected ':' after map key.")?;
+                    entries.push((key, self.assignment()?));
+
+                    if self.peek().kind != TokenKind::Coma {
+                        break;
+                    }
+                    self.advance();
+                }
 
-                Ok(Expr::Grouping(Box::new(expr)))
+                self.consume(&TokenKind::RightBrace, "Expected '}' after map entries.")?;
+                Ok(Expr::Map(brace, entries))
             }
 
+            // The string resumes where an operand was expected, such as in `"${1 + }"`.
+            TokenKind::InterpolationMiddle(_) | TokenKind::InterpolationEnd(_) => {
+                Err(Error::Parser(
+                    token.line,
+                    String::from("Expected expression inside string interpolation."),
+                ))
+            }
             _ => Err(self.err("Expected expression")),
         }
     }
@@ -276,6 +1229,46 @@ impl Parser {
         &self.tokens[self.current]
     }
 
+    /// Tells a for-in loop from a C-style one, after the opening parenthesis.
+    fn starts_for_in(&self) -> bool {
+        let keyword = self.tokens.get(self.current + 1).map(|token| &token.kind);
+        matches!(self.peek().kind, TokenKind::Identifier(_)) && keyword == Some(&TokenKind::In)
+    }
+
+    /// Tells a map literal from a block at the start of a statement: a map has a ':' after its
+    /// first key, outside of any brackets and conditional operator, which can't appear in the
+    /// first statement of a block.
+    fn starts_map(&self) -> bool {
+        let mut depth = 0;
+        let mut conditionals = 0;
+
+        for token in &self.tokens[self.current + 1..] {
+            match token.kind {
+                TokenKind::LeftParen | TokenKind::LeftBracket | TokenKind::LeftBrace => depth += 1,
+                TokenKind::RightParen | TokenKind::RightBracket | TokenKind::RightBrace
+                    if depth > 0 =>
+                {
+                    depth -= 1
+                }
+                TokenKind::Question if depth == 0 => conditionals += 1,
+                TokenKind::Colon if depth == 0 && conditionals > 0 => conditionals -= 1,
+                TokenKind::Colon if depth == 0 => return true,
+                TokenKind::RightParen
+                | TokenKind::RightBracket
+                | TokenKind::RightBrace
+                | TokenKind::Semicolon
+                    if depth == 0 =>
+                {
+                    return false
+                }
+                TokenKind::Eof => return false,
+                _ => {}
+            }
+        }
+
+        false
+    }
+
     fn consume(&mut self, kind: &TokenKind, msg: &'static str) -> RloxResult<&Token> {
         if &self.peek().kind == kind {
             Ok(self.advance())
@@ -285,7 +1278,7 @@ impl Parser {
     }
 
     fn is_at_end(&self) -> bool {
-        self.peek().kind == TokenKind::EOF
+        self.peek().kind == TokenKind::Eof
     }
 
     fn err(&self, msg: &str) -> Error {
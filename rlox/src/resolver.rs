@@ -0,0 +1,550 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::ast::{
+    BinaryOp, BindingPattern, CatchClause, ClassDecl, Expr, ExprVisitor, FunctionDecl,
+    LiteralValue, LogicalOp, MatchArm, Pattern, Stmt, StmtVisitor, Token, UnaryOp, VarRef,
+};
+use crate::result::Error;
+
+#[derive(Clone, Copy, PartialEq)]
+enum FunctionKind {
+    None,
+    Function,
+    Method,
+    Initializer,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum ClassKind {
+    None,
+    Class,
+    Subclass,
+}
+
+struct Binding {
+    defined: bool,
+    constant: bool,
+}
+
+/// Static pass run between the parser and the interpreter. It records on each variable use the
+/// number of scopes separating it from its declaration and reports static errors.
+pub struct Resolver {
+    scopes: Vec<HashMap<String, Binding>>,
+    // Constness of the globals declared so far. Globals unknown to the resolver (eg. defined by a
+    // previous REPL input) are checked at runtime.
+    globals: HashMap<String, bool>,
+    current_function: FunctionKind,
+    current_class: ClassKind,
+    errors: Vec<Error>,
+}
+
+impl Resolver {
+    pub fn resolve(statements: &[Stmt]) -> Result<(), Vec<Error>> {
+        let mut resolver = Resolver {
+            scopes: vec![],
+            globals: HashMap::new(),
+            current_function: FunctionKind::None,
+            current_class: ClassKind::None,
+            errors: vec![],
+        };
+
+        resolver.resolve_stmts(statements);
+
+        if !resolver.errors.is_empty() {
+            Err(resolver.errors)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn resolve_stmts(&mut self, statements: &[Stmt]) {
+        for statement in statements {
+            statement.accept(self);
+        }
+    }
+
+    fn resolve_expr(&mut self, expr: &Expr) {
+        expr.accept(self)
+    }
+
+    fn resolve_local(&mut self, var: &VarRef) {
+        for (depth, scope) in self.scopes.iter().rev().enumerate() {
+            if scope.contains_key(&var.id.lexeme) {
+                var.depth.set(Some(depth));
+                return;
+            }
+        }
+    }
+
+    fn resolve_assignment(&mut self, var: &VarRef) {
+        if self.is_const(&var.id) {
+            self.errors.push(Error::Resolver(
+                var.id.line,
+                format!("Cannot assign to constant '{}'.", var.id.lexeme),
+            ));
+        }
+
+        self.resolve_local(var);
+    }
+
+    fn resolve_function(&mut self, declaration: &FunctionDecl, kind: FunctionKind) {
+        let enclosing_function = self.current_function;
+        self.current_function = kind;
+
+        self.begin_scope();
+        for param in &declaration.params {
+            self.declare(param, false);
+            self.define(param);
+        }
+        self.resolve_stmts(&declaration.body);
+        self.end_scope();
+
+        self.current_function = enclosing_function;
+    }
+
+    /// Opens a scope holding a binding defined by the interpreter, such as `this` or `super`.
+    fn begin_internal_scope(&mut self, name: &str) {
+        let mut scope = HashMap::new();
+        scope.insert(
+            name.to_owned(),
+            Binding {
+                defined: true,
+                constant: true,
+            },
+        );
+
+        self.scopes.push(scope);
+    }
+
+    /// Resolves the targets of a destructuring assignment, variables are assigned rather than read.
+    fn resolve_target(&mut self, target: &Expr) {
+        match target {
+            Expr::Tuple(elements) => {
+                for element in elements {
+                    self.resolve_target(element);
+                }
+            }
+            Expr::Variable(var) => self.resolve_assignment(var),
+            _ => self.resolve_expr(target),
+        }
+    }
+
+    fn begin_scope(&mut self) {
+        self.scopes.push(HashMap::new());
+    }
+
+    fn end_scope(&mut self) {
+        self.scopes.pop();
+    }
+
+    fn declare(&mut self, id: &Token, constant: bool) {
+        let scope = match self.scopes.last_mut() {
+            Some(scope) => scope,
+            None => {
+                self.globals.insert(id.lexeme.to_owned(), constant);
+                return;
+            }
+        };
+
+        if scope.contains_key(&id.lexeme) {
+            self.errors.push(Error::Resolver(
+                id.line,
+                format!("Already a variable named '{}' in this scope.", id.lexeme),
+            ));
+        }
+
+        scope.insert(
+            id.lexeme.to_owned(),
+            Binding {
+                defined: false,
+                constant,
+            },
+        );
+    }
+
+    fn define(&mut self, id: &Token) {
+        if let Some(binding) = self
+            .scopes
+            .last_mut()
+            .and_then(|scope| scope.get_mut(&id.lexeme))
+        {
+            binding.defined = true;
+        }
+    }
+
+    fn is_const(&self, id: &Token) -> bool {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(&id.lexeme).map(|binding| binding.constant))
+            .or_else(|| self.globals.get(&id.lexeme).copied())
+            .unwrap_or(false)
+    }
+}
+
+impl ExprVisitor<()> for Resolver {
+    fn visit_binary_expr(&mut self, left: &Expr, _operator: &Token, _op: &BinaryOp, right: &Expr) {
+        self.resolve_expr(left);
+        self.resolve_expr(right);
+    }
+
+    fn visit_logical_expr(&mut self, left: &Expr, _op: &LogicalOp, right: &Expr) {
+        self.resolve_expr(left);
+        self.resolve_expr(right);
+    }
+
+    fn visit_conditional_expr(&mut self, condition: &Expr, then_branch: &Expr, else_branch: &Expr) {
+        self.resolve_expr(condition);
+        self.resolve_expr(then_branch);
+        self.resolve_expr(else_branch);
+    }
+
+    fn visit_comma_expr(&mut self, left: &Expr, right: &Expr) {
+        self.resolve_expr(left);
+        self.resolve_expr(right);
+    }
+
+    fn visit_unary_expr(&mut self, _operator: &Token, _op: &UnaryOp, expr: &Expr) {
+        self.resolve_expr(expr);
+    }
+
+    fn visit_grouping_expr(&mut self, expr: &Expr) {
+        self.resolve_expr(expr);
+    }
+
+    fn visit_literal_expr(&mut self, _value: &LiteralValue) {}
+
+    fn visit_interpolation_expr(&mut self, parts: &[Expr]) {
+        for part in parts {
+            self.resolve_expr(part);
+        }
+    }
+
+    fn visit_variable_expr(&mut self, var: &VarRef) {
+        let declared_only = self
+            .scopes
+            .last()
+            .and_then(|scope| scope.get(&var.id.lexeme))
+            .is_some_and(|binding| !binding.defined);
+
+        if declared_only {
+            self.errors.push(Error::Resolver(
+                var.id.line,
+                String::from("Can't read local variable in its own initializer."),
+            ));
+        }
+
+        self.resolve_local(var);
+    }
+
+    fn visit_assignment_expr(&mut self, var: &VarRef, expr: &Expr) {
+        self.resolve_expr(expr);
+        self.resolve_assignment(var);
+    }
+
+    fn visit_call_expr(&mut self, callee: &Expr, _paren: &Token, args: &[Expr]) {
+        self.resolve_expr(callee);
+        for arg in args {
+            self.resolve_expr(arg);
+        }
+    }
+
+    fn visit_get_expr(&mut self, object: &Expr, _name: &Token) {
+        self.resolve_expr(object);
+    }
+
+    fn visit_set_expr(&mut self, object: &Expr, _name: &Token, value: &Expr) {
+        self.resolve_expr(value);
+        self.resolve_expr(object);
+    }
+
+    fn visit_nil_coalesce_assign_expr(&mut self, target: &Expr, value: &Expr) {
+        match target {
+            Expr::Variable(var) => self.resolve_assignment(var),
+            _ => self.resolve_expr(target),
+        }
+        self.resolve_expr(value);
+    }
+
+    fn visit_compound_assign_expr(
+        &mut self,
+        target: &Expr,
+        _operator: &Token,
+        _op: &BinaryOp,
+        value: &Expr,
+        _postfix: bool,
+    ) {
+        self.visit_nil_coalesce_assign_expr(target, value);
+    }
+
+    fn visit_destructure_assign_expr(&mut self, target: &Expr, _equals: &Token, value: &Expr) {
+        self.resolve_expr(value);
+        self.resolve_target(target);
+    }
+
+    fn visit_range_expr(&mut self, start: &Expr, _operator: &Token, end: &Expr, _inclusive: bool) {
+        self.resolve_expr(start);
+        self.resolve_expr(end);
+    }
+
+    fn visit_tuple_expr(&mut self, elements: &[Expr]) {
+        for element in elements {
+            self.resolve_expr(element);
+        }
+    }
+
+    fn visit_list_expr(&mut self, elements: &[Expr]) {
+        for element in elements {
+            self.resolve_expr(element);
+        }
+    }
+
+    fn visit_map_expr(&mut self, _brace: &Token, entries: &[(Expr, Expr)]) {
+        for (key, value) in entries {
+            self.resolve_expr(key);
+            self.resolve_expr(value);
+        }
+    }
+
+    fn visit_index_expr(&mut self, object: &Expr, _bracket: &Token, index: &Expr) {
+        self.resolve_expr(object);
+        self.resolve_expr(index);
+    }
+
+    fn visit_index_set_expr(
+        &mut self,
+        object: &Expr,
+        _bracket: &Token,
+        index: &Expr,
+        value: &Expr,
+    ) {
+        self.resolve_expr(value);
+        self.resolve_expr(object);
+        self.resolve_expr(index);
+    }
+
+    fn visit_this_expr(&mut self, keyword: &VarRef) {
+        if self.current_class == ClassKind::None {
+            self.errors.push(Error::Resolver(
+                keyword.id.line,
+                String::from("Can't use 'this' outside of a class."),
+            ));
+            return;
+        }
+
+        self.resolve_local(keyword);
+    }
+
+    fn visit_super_expr(&mut self, keyword: &VarRef, _method: &Token) {
+        match self.current_class {
+            ClassKind::None => self.errors.push(Error::Resolver(
+                keyword.id.line,
+                String::from("Can't use 'super' outside of a class."),
+            )),
+            ClassKind::Class => self.errors.push(Error::Resolver(
+                keyword.id.line,
+                String::from("Can't use 'super' in a class with no superclass."),
+            )),
+            ClassKind::Subclass => self.resolve_local(keyword),
+        }
+    }
+}
+
+impl StmtVisitor<()> for Resolver {
+    fn visit_expression_stmt(&mut self, expr: &Expr) {
+        self.resolve_expr(expr);
+    }
+
+    fn visit_var_stmt(&mut self, id: &Token, initializer: &Option<Box<Expr>>) {
+        self.declare(id, false);
+        if let Some(initializer) = initializer {
+            self.resolve_expr(initializer);
+        }
+        self.define(id);
+    }
+
+    fn visit_var_destructure_stmt(
+        &mut self,
+        pattern: &BindingPattern,
+        _equals: &Token,
+        initializer: &Expr,
+    ) {
+        let names = pattern.names();
+        for name in &names {
+            self.declare(name, false);
+        }
+        self.resolve_expr(initializer);
+        for name in &names {
+            self.define(name);
+        }
+    }
+
+    fn visit_const_stmt(&mut self, id: &Token, initializer: &Expr) {
+        self.declare(id, true);
+        self.resolve_expr(initializer);
+        self.define(id);
+    }
+
+    fn visit_print_stmt(&mut self, expr: &Expr) {
+        self.resolve_expr(expr);
+    }
+
+    fn visit_block_stmt(&mut self, body: &[Stmt]) {
+        self.begin_scope();
+        self.resolve_stmts(body);
+        self.end_scope();
+    }
+
+    fn visit_if_stmt(
+        &mut self,
+        condition: &Expr,
+        then_branch: &Stmt,
+        else_branch: &Option<Box<Stmt>>,
+    ) {
+        self.resolve_expr(condition);
+        then_branch.accept(self);
+        if let Some(else_branch) = else_branch {
+            else_branch.accept(self);
+        }
+    }
+
+    fn visit_while_stmt(&mut self, condition: &Expr, body: &Stmt, increment: &Option<Box<Expr>>) {
+        self.resolve_expr(condition);
+        body.accept(self);
+        if let Some(increment) = increment {
+            self.resolve_expr(increment);
+        }
+    }
+
+    fn visit_for_in_stmt(&mut self, name: &Token, iterable: &Expr, body: &Stmt) {
+        self.resolve_expr(iterable);
+
+        // Each iteration runs in its own environment, which holds the loop variable.
+        self.begin_scope();
+        self.declare(name, false);
+        self.define(name);
+        body.accept(self);
+        self.end_scope();
+    }
+
+    fn visit_break_stmt(&mut self) {}
+
+    fn visit_continue_stmt(&mut self) {}
+
+    fn visit_function_stmt(&mut self, declaration: &Rc<FunctionDecl>) {
+        // Defined before the body is resolved, so that the function can refer to itself.
+        self.declare(&declaration.name, false);
+        self.define(&declaration.name);
+
+        self.resolve_function(declaration, FunctionKind::Function);
+    }
+
+    fn visit_return_stmt(&mut self, keyword: &Token, value: &Option<Box<Expr>>) {
+        if self.current_function == FunctionKind::None {
+            self.errors.push(Error::Resolver(
+                keyword.line,
+                String::from("Can't return from top-level code."),
+            ));
+        }
+
+        if let Some(value) = value {
+            if self.current_function == FunctionKind::Initializer {
+                self.errors.push(Error::Resolver(
+                    keyword.line,
+                    String::from("Can't return a value from an initializer."),
+                ));
+            }
+
+            self.resolve_expr(value);
+        }
+    }
+
+    fn visit_match_stmt(&mut self, _keyword: &Token, subject: &Expr, arms: &[MatchArm]) {
+        self.resolve_expr(subject);
+
+        // Each arm is evaluated in its own environment, which holds the pattern binding.
+        for arm in arms {
+            self.begin_scope();
+            if let Pattern::Binding(id) = &arm.pattern {
+                self.declare(id, false);
+                self.define(id);
+            }
+            if let Some(guard) = &arm.guard {
+                self.resolve_expr(guard);
+            }
+            arm.body.accept(self);
+            self.end_scope();
+        }
+    }
+
+    fn visit_class_stmt(&mut self, declaration: &ClassDecl) {
+        let enclosing_class = self.current_class;
+        self.current_class = ClassKind::Class;
+
+        self.declare(&declaration.name, false);
+        self.define(&declaration.name);
+
+        if let Some(superclass) = &declaration.superclass {
+            if superclass.id.lexeme == declaration.name.lexeme {
+                self.errors.push(Error::Resolver(
+                    superclass.id.line,
+                    String::from("A class can't inherit from itself."),
+                ));
+            }
+
+            self.current_class = ClassKind::Subclass;
+            self.visit_variable_expr(superclass);
+            self.begin_internal_scope("super");
+        }
+
+        self.begin_internal_scope("this");
+        for method in &declaration.methods {
+            let kind = if method.name.lexeme == "init" {
+                FunctionKind::Initializer
+            } else {
+                FunctionKind::Method
+            };
+
+            self.resolve_function(method, kind);
+        }
+        self.end_scope();
+
+        if declaration.superclass.is_some() {
+            self.end_scope();
+        }
+
+        self.current_class = enclosing_class;
+    }
+
+    fn visit_throw_stmt(&mut self, _keyword: &Token, value: &Expr) {
+        self.resolve_expr(value);
+    }
+
+    fn visit_import_stmt(&mut self, _keyword: &Token, _path: &str, alias: &Token) {
+        self.declare(alias, false);
+        self.define(alias);
+    }
+
+    fn visit_try_stmt(
+        &mut self,
+        body: &[Stmt],
+        catch: &Option<CatchClause>,
+        finally: &Option<Vec<Stmt>>,
+    ) {
+        self.visit_block_stmt(body);
+
+        // The catch body shares its environment with the exception variable.
+        if let Some(catch) = catch {
+            self.begin_scope();
+            self.declare(&catch.name, false);
+            self.define(&catch.name);
+            self.resolve_stmts(&catch.body);
+            self.end_scope();
+        }
+
+        if let Some(finally) = finally {
+            self.visit_block_stmt(finally);
+        }
+    }
+}
@@ -1,5 +1,7 @@
 use std::fmt;
 
+use crate::object::Object;
+
 /// Result type for all the rlox interfaces.
 pub type RloxResult<T> = Result<T, Error>;
 
@@ -10,8 +12,14 @@ pub enum Error {
     Scanner(usize, String),
     /// Error returned if the parser encounters an error.
     Parser(usize, String),
+    /// Error returned if the resolver encounters an error.
+    Resolver(usize, String),
     /// Error returned if the interpreter encounters an error.
     Runtime(usize, String),
+    /// Error returned if a module can't be imported, with the importing file.
+    Import(String, usize, String),
+    /// Value of a `throw` statement that no `catch` clause handled.
+    Thrown(usize, Object),
 }
 
 impl fmt::Display for Error {
@@ -19,7 +27,33 @@ impl fmt::Display for Error {
         match self {
             Error::Scanner(line, msg) => write!(f, "Scanner error [line: {}]: {}", line, msg),
             Error::Parser(line, msg) => write!(f, "Parser error [line: {}]: {}", line, msg),
+            Error::Resolver(line, msg) => write!(f, "Resolver error [line: {}]: {}", line, msg),
             Error::Runtime(line, msg) => write!(f, "Runtime error [line: {}]: {}", line, msg),
+            Error::Import(file, line, msg) => {
+                write!(f, "Import error [{}, line: {}]: {}", file, line, msg)
+            }
+            Error::Thrown(line, value) => {
+                write!(f, "Uncaught exception [line: {}]: ", line)?;
+
+                // Error instances are described by their message.
+                match value {
+                    Object::Instance(instance) => match instance.fields.borrow().get("message") {
+                        Some(message) => write!(f, "{}", message),
+                        None => write!(f, "{}", value),
+                    },
+                    _ => write!(f, "{}", value),
+                }
+            }
         }
     }
 }
+
+/// Non-fatal diagnostic reported while processing the source.
+#[derive(Debug, Clone)]
+pub struct Warning(pub usize, pub String);
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Warning [line: {}]: {}", self.0, self.1)
+    }
+}
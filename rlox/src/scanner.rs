@@ -1,6 +1,7 @@
 use std::{char, str};
 
 use crate::ast::{Token, TokenKind};
+use crate::bigint::BigInt;
 use crate::result::{Error, RloxResult};
 
 pub struct Scanner<'a> {
@@ -9,6 +10,10 @@ pub struct Scanner<'a> {
     current: usize,
     start: usize,
     line: usize,
+    // Line of the start of the current token, which can span several lines.
+    start_line: usize,
+    // Brace depth inside each string interpolation being scanned, innermost last.
+    interpolations: Vec<usize>,
 }
 
 impl Scanner<'_> {
@@ -19,6 +24,8 @@ impl Scanner<'_> {
             current: 0,
             start: 0,
             line: 1,
+            start_line: 1,
+            interpolations: Vec::new(),
         };
 
         scanner.scan_tokens()?;
@@ -32,10 +39,15 @@ impl Scanner<'_> {
     fn scan_tokens(&mut self) -> RloxResult<()> {
         while !self.is_at_end() {
             self.start = self.current;
+            self.start_line = self.line;
             self.scan_token()?;
         }
 
-        self.add_token(TokenKind::EOF);
+        if !self.interpolations.is_empty() {
+            return Err(self.err("Unterminated string interpolation."));
+        }
+
+        self.add_token(TokenKind::Eof);
         Ok(())
     }
 
@@ -45,14 +57,71 @@ impl Scanner<'_> {
         match c {
             '(' => self.add_token(TokenKind::LeftParen),
             ')' => self.add_token(TokenKind::RightParen),
-            '{' => self.add_token(TokenKind::LeftBrace),
-            '}' => self.add_token(TokenKind::RightBrace),
+            '{' => {
+                if let Some(depth) = self.interpolations.last_mut() {
+                    *depth += 1;
+                }
+                self.add_token(TokenKind::LeftBrace)
+            }
+            '}' => match self.interpolations.last_mut() {
+                // Closes the interpolated expression, the rest of the string follows.
+                Some(0) => {
+                    self.interpolations.pop();
+                    self.process_string(true)?
+                }
+                Some(depth) => {
+                    *depth -= 1;
+                    self.add_token(TokenKind::RightBrace)
+                }
+                None => self.add_token(TokenKind::RightBrace),
+            },
+            '[' => self.add_token(TokenKind::LeftBracket),
+            ']' => self.add_token(TokenKind::RightBracket),
             ',' => self.add_token(TokenKind::Coma),
-            '.' => self.add_token(TokenKind::Dot),
-            '-' => self.add_token(TokenKind::Minus),
-            '+' => self.add_token(TokenKind::Plus),
+            ':' => self.add_token(TokenKind::Colon),
+            '.' => {
+                if self.matches('.') {
+                    if self.matches('=') {
+                        self.add_token(TokenKind::DotDotEqual)
+                    } else {
+                        self.add_token(TokenKind::DotDot)
+                    }
+                } else {
+                    self.add_token(TokenKind::Dot)
+                }
+            }
+            '-' => {
+                if self.matches('-') {
+                    self.add_token(TokenKind::MinusMinus)
+                } else if self.matches('=') {
+                    self.add_token(TokenKind::MinusEqual)
+                } else {
+                    self.add_token(TokenKind::Minus)
+                }
+            }
+            '+' => {
+                if self.matches('+') {
+                    self.add_token(TokenKind::PlusPlus)
+                } else if self.matches('=') {
+                    self.add_token(TokenKind::PlusEqual)
+                } else {
+                    self.add_token(TokenKind::Plus)
+                }
+            }
             ';' => self.add_token(TokenKind::Semicolon),
-            '*' => self.add_token(TokenKind::Star),
+            '*' => {
+                if self.matches('*') {
+                    self.add_token(TokenKind::StarStar)
+                } else if self.matches('=') {
+                    self.add_token(TokenKind::StarEqual)
+                } else {
+                    self.add_token(TokenKind::Star)
+                }
+            }
+            '%' => self.add_token(TokenKind::Percent),
+            '&' => self.add_token(TokenKind::Ampersand),
+            '|' => self.add_token(TokenKind::Pipe),
+            '^' => self.add_token(TokenKind::Caret),
 
             '!' => {
                 if self.matches('=') {
@@ -64,6 +133,8 @@ impl Scanner<'_> {
             '=' => {
                 if self.matches('=') {
                     self.add_token(TokenKind::EqualEqual)
+                } else if self.matches('>') {
+                    self.add_token(TokenKind::FatArrow)
                 } else {
                     self.add_token(TokenKind::Equal)
                 }
@@ -71,6 +142,8 @@ impl Scanner<'_> {
             '<' => {
                 if self.matches('=') {
                     self.add_token(TokenKind::LessEqual)
+                } else if self.matches('<') {
+                    self.add_token(TokenKind::LessLess)
                 } else {
                     self.add_token(TokenKind::Less)
                 }
@@ -78,15 +151,36 @@ impl Scanner<'_> {
             '>' => {
                 if self.matches('=') {
                     self.add_token(TokenKind::GreaterEqual)
+                } else if self.matches('>') {
+                    self.add_token(TokenKind::GreaterGreater)
                 } else {
                     self.add_token(TokenKind::Greater)
                 }
             }
+            '?' if self.matches('?') => {
+                if self.matches('=') {
+                    self.add_token(TokenKind::QuestionQuestionEqual)
+                } else {
+                    self.add_token(TokenKind::QuestionQuestion)
+                }
+            }
+            '?' => self.add_token(TokenKind::Question),
+            '~' => {
+                if self.matches('/') {
+                    self.add_token(TokenKind::TildeSlash)
+                } else {
+                    self.add_token(TokenKind::Tilde)
+                }
+            }
             '/' => {
                 if self.matches('/') {
                     while self.peek() != Some('\n') && !self.is_at_end() {
                         self.advance();
                     }
+                } else if self.matches('*') {
+                    self.process_block_comment()?
+                } else if self.matches('=') {
+                    self.add_token(TokenKind::SlashEqual)
                 } else {
                     self.add_token(TokenKind::Slash)
                 }
@@ -95,7 +189,8 @@ impl Scanner<'_> {
             ' ' | '\r' | '\t' => {}
             '\n' => self.line += 1,
 
-            '"' => self.process_string()?,
+            '"' => self.process_string(false)?,
+            'r' if self.matches('"') => self.process_raw_string()?,
             c if Self::is_digit(c) => self.process_number()?,
             c if Self::is_alpha(c) => self.process_identifier()?,
 
@@ -107,7 +202,110 @@ impl Scanner<'_> {
         Ok(())
     }
 
-    fn process_string(&mut self) -> RloxResult<()> {
+    /// Scans a string segment, starting after the opening quote or, when `continued`, after the
+    /// '}' closing an interpolated expression. A segment followed by `${` is emitted as an
+    /// interpolation token, the expression tokens come next.
+    fn process_string(&mut self, continued: bool) -> RloxResult<()> {
+        // Built from bytes, so that multi-byte characters are copied intact.
+        let mut value = Vec::new();
+        loop {
+            match self.peek() {
+                None => return Err(self.err_at_start("Unterminated string.")),
+                Some('"') => break,
+                Some('$') if self.peek_next() == Some('{') => {
+                    self.advance();
+                    self.advance();
+                    let value = self.to_string(value)?;
+                    self.add_token(if continued {
+                        TokenKind::InterpolationMiddle(value)
+                    } else {
+                        TokenKind::Interpolation(value)
+                    });
+                    self.interpolations.push(0);
+
+                    return Ok(());
+                }
+                Some('\\') => {
+                    self.advance();
+                    let c = self.process_escape()?;
+                    value.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+                }
+                Some(c) => {
+                    if c == '\n' {
+                        self.line += 1;
+                    }
+
+                    self.advance();
+                    value.push(self.source[self.current - 1]);
+                }
+            }
+        }
+
+        self.advance();
+        let value = self.to_string(value)?;
+        self.add_token(if continued {
+            TokenKind::InterpolationEnd(value)
+        } else {
+            TokenKind::String(value)
+        });
+
+        Ok(())
+    }
+
+    /// Scans the character following a backslash.
+    fn process_escape(&mut self) -> RloxResult<char> {
+        let c = match self.peek() {
+            Some(c) => c,
+            None => return Err(self.err_at_start("Unterminated string.")),
+        };
+        self.advance();
+
+        match c {
+            'n' => Ok('\n'),
+            't' => Ok('\t'),
+            'r' => Ok('\r'),
+            '0' => Ok('\0'),
+            '\\' => Ok('\\'),
+            '"' => Ok('"'),
+            '$' => Ok('$'),
+            'u' => self.process_unicode_escape(),
+            c if c.is_ascii_graphic() => {
+                Err(self.err(&format!("Invalid escape sequence '\\{}'.", c)))
+            }
+            _ => Err(self.err("Invalid escape sequence.")),
+        }
+    }
+
+    /// Scans the `{XXXX}` part of a `\u{XXXX}` escape, holding 1 to 6 hexadecimal digits.
+    fn process_unicode_escape(&mut self) -> RloxResult<char> {
+        if !self.matches('{') {
+            return Err(self.err("Expected '{' after '\\u'."));
+        }
+
+        let start = self.current;
+        while let Some(c) = self.peek() {
+            if c.is_ascii_hexdigit() {
+                self.advance();
+            } else {
+                break;
+            }
+        }
+        let digits = &self.source[start..self.current];
+
+        if !self.matches('}') || digits.is_empty() || digits.len() > 6 {
+            return Err(self.err("Unicode escape must be 1 to 6 hexadecimal digits in braces."));
+        }
+
+        str::from_utf8(digits)
+            .ok()
+            .and_then(|digits| u32::from_str_radix(digits, 16).ok())
+            .and_then(char::from_u32)
+            .ok_or_else(|| self.err("Invalid unicode character in escape sequence."))
+    }
+
+    /// Scans a raw string, starting after `r"`. Its content is taken verbatim, without escape
+    /// sequences nor interpolation.
+    fn process_raw_string(&mut self) -> RloxResult<()> {
         while self.peek() != Some('"') && !self.is_at_end() {
             if self.peek() == Some('\n') {
                 self.line += 1
@@ -117,51 +315,156 @@ impl Scanner<'_> {
         }
 
         if self.is_at_end() {
-            return Err(self.err("Unterminated string"));
+            return Err(self.err_at_start("Unterminated string."));
         }
 
         self.advance();
         self.add_token(TokenKind::String(
-            str::from_utf8(&self.source[self.start + 1..self.current - 1])
-                .map_err(|_| self.err("Invalid string"))?
-                .to_string(),
+            self.to_string(self.source[self.start + 2..self.current - 1].to_vec())?,
         ));
 
         Ok(())
     }
 
+    /// Skips a block comment, starting after the opening `/*`. Block comments nest.
+    fn process_block_comment(&mut self) -> RloxResult<()> {
+        let mut depth = 1;
+        while depth > 0 {
+            match self.peek() {
+                None => return Err(self.err_at_start("Unterminated block comment.")),
+                Some('/') if self.peek_next() == Some('*') => {
+                    self.advance();
+                    depth += 1;
+                }
+                Some('*') if self.peek_next() == Some('/') => {
+                    self.advance();
+                    depth -= 1;
+                }
+                Some('\n') => self.line += 1,
+                _ => {}
+            }
+
+            self.advance();
+        }
+
+        Ok(())
+    }
+
     fn process_number(&mut self) -> RloxResult<()> {
-        while let Some(c) = self.peek() {
-            if Self::is_digit(c) {
+        let radix = match (self.source[self.start], self.peek()) {
+            (b'0', Some('x' | 'X')) => Some((16, "hexadecimal")),
+            (b'0', Some('o' | 'O')) => Some((8, "octal")),
+            (b'0', Some('b' | 'B')) => Some((2, "binary")),
+            _ => None,
+        };
+
+        let kind = match radix {
+            Some((radix, name)) => {
                 self.advance();
-            } else {
-                break;
+                self.process_radix_number(radix, name)?
             }
+            None => self.process_decimal_number()?,
+        };
+
+        self.add_token(kind);
+        Ok(())
+    }
+
+    /// Scans the digits of a `0x`, `0o` or `0b` literal, starting after the prefix. These are
+    /// always integers.
+    fn process_radix_number(&mut self, radix: u32, name: &str) -> RloxResult<TokenKind> {
+        let digits = self.process_digits(radix)?;
+        if digits.is_empty() {
+            return Err(self.err(&format!(
+                "Expected {} digits after '{}'.",
+                name,
+                &self.lexeme()[..2]
+            )));
         }
 
-        if self.peek() == Some('.') {
-            if let Some(c) = self.peek_next() {
-                if Self::is_digit(c) {
-                    self.advance();
-                    while let Some(c) = self.peek() {
-                        if Self::is_digit(c) {
-                            self.advance();
-                        } else {
-                            break;
-                        }
-                    }
+        if let Some(c) = self.peek().filter(|c| Self::is_alpha_numeric(*c)) {
+            return Err(self.err(&format!("Invalid digit '{}' in {} literal.", c, name)));
+        }
+
+        Ok(Self::integer_token(&digits, radix))
+    }
+
+    /// Scans a decimal literal, which is an integer unless it has a fraction or an exponent.
+    fn process_decimal_number(&mut self) -> RloxResult<TokenKind> {
+        // The first digit is scanned again, so that separators are checked from the start.
+        self.current = self.start;
+        let mut literal = self.process_digits(10)?;
+
+        if self.peek() == Some('.') && self.peek_next().is_some_and(Self::is_digit) {
+            self.advance();
+            literal.push('.');
+            literal.push_str(&self.process_digits(10)?);
+        }
+
+        if let Some(e @ ('e' | 'E')) = self.peek() {
+            self.advance();
+            literal.push(e);
+            if let Some(sign @ ('+' | '-')) = self.peek() {
+                self.advance();
+                literal.push(sign);
+            }
+
+            if !self.peek().is_some_and(Self::is_digit) {
+                return Err(self.err("Expected digits in number exponent."));
+            }
+            literal.push_str(&self.process_digits(10)?);
+        }
+
+        if let Some(c) = self.peek().filter(|c| Self::is_alpha_numeric(*c)) {
+            return Err(self.err(&format!("Invalid character '{}' in number literal.", c)));
+        }
+
+        if !literal.contains(['.', 'e', 'E']) {
+            return Ok(Self::integer_token(&literal, 10));
+        }
+
+        match literal.parse::<f64>() {
+            Ok(value) if value.is_finite() => Ok(TokenKind::Number(value)),
+            _ => Err(self.err("Number literal is too large.")),
+        }
+    }
+
+    /// Integers that don't fit in an i64 become big integers.
+    fn integer_token(digits: &str, radix: u32) -> TokenKind {
+        match i64::from_str_radix(digits, radix) {
+            Ok(value) => TokenKind::Integer(value),
+            Err(_) => TokenKind::BigInt(
+                BigInt::parse(digits, radix).expect("digits are checked by the scanner"),
+            ),
+        }
+    }
+
+    /// Scans a run of digits in the radix, returning them without their `_` separators. A
+    /// separator must sit between two digits.
+    fn process_digits(&mut self, radix: u32) -> RloxResult<String> {
+        let mut digits = String::new();
+        let mut separated = false;
+        while let Some(c) = self.peek() {
+            if c == '_' {
+                if digits.is_empty() || separated {
+                    return Err(self.err("Digit separator '_' must be between digits."));
                 }
+                separated = true;
+            } else if c.is_digit(radix) {
+                digits.push(c);
+                separated = false;
+            } else {
+                break;
             }
+
+            self.advance();
         }
 
-        self.add_token(TokenKind::Number(
-            str::from_utf8(&self.source[self.start..self.current])
-                .map_err(|_| self.err("Invalid number."))?
-                .parse()
-                .map_err(|_| self.err("Invalid number."))?,
-        ));
+        if separated {
+            return Err(self.err("Digit separator '_' must be between digits."));
+        }
 
-        Ok(())
+        Ok(digits)
     }
 
     fn process_identifier(&mut self) -> RloxResult<()> {
@@ -178,19 +481,30 @@ impl Scanner<'_> {
 
         self.add_token(match value {
             "and" => TokenKind::And,
+            "as" => TokenKind::As,
+            "break" => TokenKind::Break,
+            "catch" => TokenKind::Catch,
             "class" => TokenKind::Class,
+            "const" => TokenKind::Const,
+            "continue" => TokenKind::Continue,
             "else" => TokenKind::Else,
             "false" => TokenKind::False,
+            "finally" => TokenKind::Finally,
             "for" => TokenKind::For,
             "fun" => TokenKind::Function,
             "if" => TokenKind::If,
+            "import" => TokenKind::Import,
+            "in" => TokenKind::In,
+            "match" => TokenKind::Match,
             "nil" => TokenKind::Nil,
             "or" => TokenKind::Or,
             "print" => TokenKind::Print,
             "return" => TokenKind::Return,
             "super" => TokenKind::Super,
             "this" => TokenKind::This,
+            "throw" => TokenKind::Throw,
             "true" => TokenKind::True,
+            "try" => TokenKind::Try,
             "var" => TokenKind::Var,
             "while" => TokenKind::While,
             _ => TokenKind::Identifier(value.to_string()),
@@ -202,13 +516,15 @@ impl Scanner<'_> {
     fn add_token(&mut self, token: TokenKind) {
         self.tokens.push(Token {
             kind: token,
-            line: self.line,
-            lexeme: str::from_utf8(&self.source[self.start..self.current])
-                .unwrap()
-                .to_owned(),
+            line: self.start_line,
+            lexeme: self.lexeme().to_owned(),
         });
     }
 
+    fn lexeme(&self) -> &str {
+        str::from_utf8(&self.source[self.start..self.current]).unwrap()
+    }
+
     fn advance(&mut self) -> char {
         self.current += 1;
         self.source[self.current - 1] as char
@@ -243,12 +559,21 @@ impl Scanner<'_> {
         Error::Scanner(self.line, String::from(msg))
     }
 
+    /// Reports an error at the line where the current token starts.
+    fn err_at_start(&self, msg: &str) -> Error {
+        Error::Scanner(self.start_line, String::from(msg))
+    }
+
+    fn to_string(&self, bytes: Vec<u8>) -> RloxResult<String> {
+        String::from_utf8(bytes).map_err(|_| self.err("Invalid string."))
+    }
+
     fn is_digit(c: char) -> bool {
-        c.is_digit(10)
+        c.is_ascii_digit()
     }
 
     fn is_alpha(c: char) -> bool {
-        c >= 'a' && c <= 'z' || c >= 'A' && c <= 'Z' || c == '_'
+        c.is_ascii_alphabetic() || c == '_'
     }
 
     fn is_alpha_numeric(c: char) -> bool {
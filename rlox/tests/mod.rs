@@ -1,6 +1,10 @@
 extern crate rlox;
 
-use std::{fs, str};
+use std::io::Write;
+use std::{fs, str, thread};
+
+use rlox::interpreter::{Interpreter, STACK_SIZE};
+use rlox::object::Object;
 
 fn load_fixture(path: &str) -> Option<String> {
     fs::read_to_string(path).ok()
@@ -12,9 +16,28 @@ fn run(in_file: &str) {
 
     let out_file = &format!("{}.out", in_file);
     let expected_out = load_fixture(out_file);
-    let mut actual_out = vec![];
 
-    rlox::evaluate(&input, &mut actual_out).unwrap();
+    // Test threads are too small for the maximum call depth.
+    let actual_out = thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(move || {
+            let mut actual_out = vec![];
+            let mut warnings = vec![];
+            let result = rlox::evaluate(&input, &mut actual_out, |warning| warnings.push(warning));
+
+            // Errors and warnings are part of the expected output, so that diagnostics can be
+            // covered by fixtures.
+            for warning in warnings {
+                writeln!(actual_out, "{}", warning).unwrap();
+            }
+            if let Err(err) = result {
+                writeln!(actual_out, "{}", err).unwrap();
+            }
+            actual_out
+        })
+        .unwrap()
+        .join()
+        .unwrap();
 
     // Generate actual output file is not present.
     if expected_out.is_none() {
@@ -45,5 +68,129 @@ test_fixture! {
     arithmetic => "arithmetic.lox",
     comparison => "comparison.lox",
     variables => "variables.lox",
-    scope => "scope.lox"
+    scope => "scope.lox",
+    conditionals => "conditionals.lox",
+    logical => "logical.lox",
+    while_loop => "while.lox",
+    for_loop => "for.lox",
+    break_continue => "break_continue.lox",
+    break_outside_loop => "break_outside_loop.lox",
+    constant => "const.lox",
+    const_assign => "const_assign.lox",
+    const_missing_initializer => "const_missing_initializer.lox",
+    const_assign_runtime => "const_assign_runtime.lox",
+    functions => "functions.lox",
+    closures => "closures.lox",
+    function_arity => "function_arity.lox",
+    call_non_function => "call_non_function.lox",
+    return_top_level => "return_top_level.lox",
+    match_statement => "match.lox",
+    match_no_arm => "match_no_arm.lox",
+    match_unreachable_arm => "match_unreachable_arm.lox",
+    resolver => "resolver.lox",
+    resolver_own_initializer => "resolver_own_initializer.lox",
+    resolver_duplicate_local => "resolver_duplicate_local.lox",
+    resolver_duplicate_param => "resolver_duplicate_param.lox",
+    classes => "classes.lox",
+    inheritance => "inheritance.lox",
+    class_inherit_non_class => "class_inherit_non_class.lox",
+    class_inherit_self => "class_inherit_self.lox",
+    class_this_outside => "class_this_outside.lox",
+    class_super_no_superclass => "class_super_no_superclass.lox",
+    class_init_return_value => "class_init_return_value.lox",
+    class_undefined_property => "class_undefined_property.lox",
+    class_property_non_instance => "class_property_non_instance.lox",
+    nil_coalesce => "nil_coalesce.lox",
+    nil_coalesce_invalid_target => "nil_coalesce_invalid_target.lox",
+    nil_coalesce_const => "nil_coalesce_const.lox",
+    natives => "natives.lox",
+    native_arity => "native_arity.lox",
+    conditional => "conditional.lox",
+    comma => "comma.lox",
+    lists => "lists.lox",
+    list_out_of_bounds => "list_out_of_bounds.lox",
+    list_invalid_index => "list_invalid_index.lox",
+    list_index_non_list => "list_index_non_list.lox",
+    maps => "maps.lox",
+    map_nan_key => "map_nan_key.lox",
+    interpolation => "interpolation.lox",
+    interpolation_unterminated => "interpolation_unterminated.lox",
+    interpolation_missing_brace => "interpolation_missing_brace.lox",
+    string_escapes => "string_escapes.lox",
+    string_invalid_escape => "string_invalid_escape.lox",
+    string_invalid_unicode => "string_invalid_unicode.lox",
+    string_unterminated => "string_unterminated.lox",
+    block_comments => "block_comments.lox",
+    block_comment_unterminated => "block_comment_unterminated.lox",
+    number_literals => "number_literals.lox",
+    number_missing_hex_digits => "number_missing_hex_digits.lox",
+    number_invalid_binary_digit => "number_invalid_binary_digit.lox",
+    number_trailing_separator => "number_trailing_separator.lox",
+    number_missing_exponent => "number_missing_exponent.lox",
+    number_too_large => "number_too_large.lox",
+    number_invalid_suffix => "number_invalid_suffix.lox",
+    number_precision => "number_precision.lox",
+    integers => "integers.lox",
+    integer_overflow => "integer_overflow.lox",
+    integer_division_by_zero => "integer_division_by_zero.lox",
+    list_float_index => "list_float_index.lox",
+    bignum => "bignum.lox",
+    rational_zero_denominator => "rational_zero_denominator.lox",
+    rational_float_argument => "rational_float_argument.lox",
+    rational_division_by_zero => "rational_division_by_zero.lox",
+    operators => "operators.lox",
+    bitwise_float_operand => "bitwise_float_operand.lox",
+    bitwise_big_operand => "bitwise_big_operand.lox",
+    shift_negative => "shift_negative.lox",
+    modulo_non_number => "modulo_non_number.lox",
+    modulo_by_zero => "modulo_by_zero.lox",
+    compound_assignment => "compound_assignment.lox",
+    increment_invalid_target => "increment_invalid_target.lox",
+    compound_assignment_invalid_target => "compound_assignment_invalid_target.lox",
+    compound_assignment_const => "compound_assignment_const.lox",
+    exceptions => "exceptions.lox",
+    throw_uncaught => "throw_uncaught.lox",
+    throw_uncaught_finally => "throw_uncaught_finally.lox",
+    try_without_catch => "try_without_catch.lox",
+    imports => "imports.lox",
+    import_missing => "import_missing.lox",
+    import_cycle => "import_cycle.lox",
+    import_without_alias => "import_without_alias.lox",
+    for_in => "for_in.lox",
+    for_in_not_iterable => "for_in_not_iterable.lox",
+    range_non_integer => "range_non_integer.lox",
+    destructuring => "destructuring.lox",
+    destructuring_arity => "destructuring_arity.lox",
+    destructuring_not_tuple => "destructuring_not_tuple.lox",
+    destructuring_invalid_target => "destructuring_invalid_target.lox",
+    destructuring_const => "destructuring_const.lox",
+    stack_overflow => "stack_overflow.lox",
+    list_self_reference => "list_self_reference.lox",
+    map_self_reference => "map_self_reference.lox",
+    map_statement_keys => "map_statement_keys.lox",
+    integer_too_large => "integer_too_large.lox",
+    deep_recursion => "deep_recursion.lox",
+    import_syntax_error => "import_syntax_error.lox",
+    match_arm_commas => "match_arm_commas.lox",
+    interpolation_missing_operand => "interpolation_missing_operand.lox"
+}
+
+#[test]
+fn host_natives() {
+    let mut out = vec![];
+    let mut interpreter = Interpreter::new(&mut out);
+
+    interpreter.define_native("hypot", 2, |args| match (&args[0], &args[1]) {
+        (Object::Number(a), Object::Number(b)) => Ok(Object::Number(a.hypot(*b))),
+        _ => Err(String::from("Arguments must be numbers.")),
+    });
+
+    rlox::run(&mut interpreter, "print hypot(3.0, 4.0);", |_| {}).unwrap();
+    let err = rlox::run(&mut interpreter, "hypot(3.0, \"4\");", |_| {}).unwrap_err();
+
+    assert_eq!(
+        err.to_string(),
+        "Runtime error [line: 1]: Arguments must be numbers."
+    );
+    assert_eq!(str::from_utf8(&out).unwrap(), "5\n");
 }